@@ -0,0 +1,57 @@
+//! Random playouts and state clones, the loop search bots spend their time in.
+//!
+//! cargo bench --bench playouts
+use std::time::{Duration, Instant};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use engine::{Game, Options, Phase, Setup};
+
+const DURATION: Duration = Duration::from_secs(3);
+const CLONES: u32 = 200_000;
+
+fn main() {
+    let expansions = Options {
+        with_promo_wonders: true,
+        with_pantheon: true,
+        with_agora: true,
+    };
+
+    for (name, o) in [("base", Options::default()), ("expansions", expansions)] {
+        let root = Game::new(Setup::from_seed(1, 2, o, 7)).expect("valid setup");
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut playouts = 0u32;
+        let mut moves = 0u32;
+        let started = Instant::now();
+
+        while started.elapsed() < DURATION {
+            let mut game = root.clone();
+
+            while game.phase() != Phase::Over {
+                let action = game.legal_actions().choose(&mut rng).cloned().expect("moves are offered until the end");
+                game.apply(game.turn(), action).expect("legal move is accepted");
+                moves += 1;
+            }
+
+            playouts += 1;
+        }
+
+        let elapsed = started.elapsed().as_secs_f64();
+        println!("{:<10} playouts/s {:>8.0}  moves/s {:>10.0}", name, playouts as f64 / elapsed, moves as f64 / elapsed);
+
+        // middle of age II, cities and the pyramid are half full
+        let mut game = root.clone();
+
+        for _ in 0..40 {
+            let action = game.legal_actions().choose(&mut rng).cloned().expect("moves are offered until the end");
+            game.apply(game.turn(), action).expect("legal move is accepted");
+        }
+
+        let started = Instant::now();
+
+        for _ in 0..CLONES {
+            std::hint::black_box(game.clone());
+        }
+
+        println!("{:<10} clones/s   {:>8.0}", name, CLONES as f64 / started.elapsed().as_secs_f64());
+    }
+}
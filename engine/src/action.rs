@@ -1,19 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 use rand::prelude::{*};
+use rand_chacha::ChaCha8Rng;
 use crate::{
     prelude::*,
+    agora::{self, Agora},
+    error::SetupError,
     building,
+    conspiracy,
+    deck::get_face_down_slots,
+    divinity::{self, Mythology},
+    effect::get_constructed_wonders_count,
+    pantheon::{self, Pantheon},
     wonder,
     token,
     player::Finisher,
-    state::{self, after},
+    state::{self, after, Cities},
 };
 
-
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+// setup is applied once per game, boxing it only adds noise to the callers
+#[allow(clippy::large_enum_variant)]
 pub enum Action {
     Prepare(Setup),
-    // resign, timeout. (loser, reason)
+    // loser
     Resign(Nickname),
+    // loser, time is kept by the caller, see Game::check_clock
+    Timeout(Nickname),
     SelectWhoBeginsTheNextAge(Nickname),
     ConstructWonder(wonder::Id, building::Id),
     ConstructBuilding(building::Id),
@@ -26,29 +40,368 @@ pub enum Action {
     PickDiscardedBuilding(building::Id),
     // pick, give
     PickReturnedBuildings(building::Id, building::Id),
+    // divinity, pantheon space
+    PickDivinity(divinity::Id, usize),
+    ActivateDivinity(divinity::Id),
+    ActivateGate,
+    ConstructDiscardedWonder(wonder::Id, building::Id),
+    DestructWonder(wonder::Id),
+    StealWonder(wonder::Id),
+    DiscardLayoutBuilding(building::Id),
+    StealBuilding(building::Id),
+    PickScientificSymbol(ScientificSymbol),
+    // senate chamber
+    PlaceInfluence(usize),
+    // from, to
+    MoveInfluence(usize, usize),
+    RemoveInfluence(usize),
+    PickConspiracy(conspiracy::Id),
+    TriggerConspiracy(conspiracy::Id),
 }
 
 impl Action {
-    pub fn apply(self, s: &mut State) -> Result<(), Error> {
+    // all checks go before any change, so a rejected action leaves the state untouched
+    pub fn apply(self, s: &mut State) -> Result<Vec<GameEvent>, Error> {
+        self.validate(s)?;
+        self.commit(s);
+
+        Ok(std::mem::take(&mut s.events))
+    }
+
+    // for moves made by players, a trusted log can be replayed with apply
+    pub fn apply_by(self, s: &mut State, actor: Nickname) -> Result<Vec<GameEvent>, Error> {
+        self.validate_actor(s, actor)?;
+        self.apply(s)
+    }
+
+    pub fn validate_actor(&self, s: &State, actor: Nickname) -> Result<(), Error> {
+        if !s.cities.contains_key(&actor) {
+            return Err(Error::UnknownPlayer(actor));
+        }
+
         match self {
-            Self::Prepare(v) => {
-                if s.phase != Phase::None {
-                    return Err(Error::ActionNotAllowed);
+            // either player can resign at any time, but only for themselves
+            Self::Resign(loser) => {
+                if *loser != actor {
+                    return Err(Error::NotYourTurn);
+                }
+            }
+
+            // the time runs out only by the clock, see Game::check_clock
+            Self::Timeout(_) => return Err(Error::TimeoutByClockOnly),
+
+            _ => {
+                if actor != s.players.me {
+                    return Err(Error::NotYourTurn);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self, s: &State) -> Result<(), Error> {
+        if s.phase == Phase::Over {
+            return Err(Error::GameOver);
+        }
+
+        match self {
+            Self::Prepare(setup) => {
+                Self::expect_phase(s, Phase::None)?;
+                setup.validate().map_err(Error::InvalidSetup)?;
+            }
+
+            Self::Resign(loser) | Self::Timeout(loser) => {
+                if s.phase == Phase::None {
+                    return Err(Error::GameNotStarted);
+                }
+
+                Self::expect_seated(s, loser)?;
+            }
+
+            Self::SelectWhoBeginsTheNextAge(p) => {
+                Self::expect_phase(s, Phase::WhoBeginsTheNextAgeSelection)?;
+                Self::expect_seated(s, p)?;
+            }
+
+            Self::ConstructWonder(wid, bid) => {
+                Self::expect_playable(s, bid)?;
+                Self::expect_constructable_wonder(s, wid)?;
+                Self::expect_coins(s, s.get_price(PayScope::Wonders, get_wonder(wid).cost.clone()))?;
+            }
+
+            Self::ConstructBuilding(bid) => {
+                Self::expect_playable(s, bid)?;
+
+                if !s.me().chains.contains(bid) {
+                    Self::expect_coins(s, s.get_price(PayScope::from_building(bid), get_building(bid).cost.clone()))?;
+                }
+            }
+
+            Self::DiscardBuilding(bid) => {
+                Self::expect_playable(s, bid)?;
+            }
+
+            Self::DestructBuilding(bid) => {
+                Self::validate_building_choice(s, Phase::DestructBuildingSelection, bid)?;
+            }
+
+            Self::PickWonder(wid) => {
+                Self::expect_phase(s, Phase::WondersSelection)?;
+
+                if !s.interactive_units.wonders.contains(&Some(*wid)) {
+                    return Err(Error::UnknownChoice);
+                }
+            }
+
+            Self::PickBoardToken(tid) => {
+                Self::validate_token_choice(s, Phase::BoardTokenSelection, tid)?;
+            }
+
+            Self::PickRandomToken(tid) => {
+                Self::validate_token_choice(s, Phase::RandomTokenSelection, tid)?;
+            }
+
+            Self::PickTopLineBuilding(bid) => {
+                Self::validate_building_choice(s, Phase::TopLineBuildingSelection, bid)?;
+            }
+
+            Self::PickDiscardedBuilding(bid) => {
+                Self::validate_building_choice(s, Phase::DiscardedBuildingSelection, bid)?;
+            }
+
+            Self::PickReturnedBuildings(pick, give) => {
+                Self::validate_building_choice(s, Phase::ReturnedBuildingSelection, pick)?;
+                Self::validate_building_choice(s, Phase::ReturnedBuildingSelection, give)?;
+
+                if pick == give {
+                    return Err(Error::UnknownChoice);
+                }
+            }
+
+            Self::PickDivinity(id, space) => {
+                Self::expect_phase(s, Phase::DivinitySelection)?;
+
+                let is_free_space = s.pantheon.as_ref()
+                    .is_some_and(|p| p.board.free_spaces().contains(space));
+
+                if !s.interactive_units.divinities.contains(id) || !is_free_space {
+                    return Err(Error::UnknownChoice);
+                }
+            }
+
+            Self::ActivateDivinity(id) => {
+                if s.phase == Phase::DivinityActivationSelection {
+                    if !s.interactive_units.divinities.contains(id) {
+                        return Err(Error::UnknownChoice);
+                    }
+
+                    return Ok(());
+                }
+
+                Self::expect_phase(s, Phase::Turn)?;
+
+                let space = s.pantheon.as_ref()
+                    .filter(|_| s.age >= pantheon::ACTIVATION_AGE)
+                    .and_then(|p| p.board.find(id))
+                    .ok_or(Error::DivinityNotAvailable(*id))?;
+
+                Self::expect_coins(s, s.get_divinity_price(space))?;
+            }
+
+            Self::ActivateGate => {
+                Self::expect_phase(s, Phase::Turn)?;
+
+                let space = s.pantheon.as_ref()
+                    .filter(|p| s.age >= pantheon::ACTIVATION_AGE && p.has_gate_choice())
+                    .and_then(|p| p.board.gate)
+                    .ok_or(Error::GateNotAvailable)?;
+
+                Self::expect_coins(s, s.get_divinity_price(space))?;
+            }
+
+            Self::ConstructDiscardedWonder(wid, bid) => {
+                Self::validate_building_choice(s, Phase::DiscardedWonderSelection, bid)?;
+                Self::expect_constructable_wonder(s, wid)?;
+            }
+
+            Self::DestructWonder(wid) => {
+                Self::validate_wonder_choice(s, Phase::DestructWonderSelection, wid)?;
+            }
+
+            Self::StealWonder(wid) => {
+                Self::validate_wonder_choice(s, Phase::StealWonderSelection, wid)?;
+            }
+
+            Self::DiscardLayoutBuilding(bid) => {
+                Self::validate_building_choice(s, Phase::LayoutBuildingSelection, bid)?;
+            }
+
+            Self::StealBuilding(bid) => {
+                Self::validate_building_choice(s, Phase::StealBuildingSelection, bid)?;
+            }
+
+            Self::PickScientificSymbol(symbol) => {
+                Self::expect_phase(s, Phase::ScientificSymbolSelection)?;
+
+                if !s.interactive_units.symbols.contains(symbol) {
+                    return Err(Error::UnknownChoice);
+                }
+            }
+
+            Self::PlaceInfluence(chamber) => {
+                // conspirator lets to place a cube instead of the conspiracy
+                if s.phase != Phase::ConspiracySelection {
+                    Self::expect_phase(s, Phase::SenateSelection)?;
+                }
+
+                if *chamber >= agora::CHAMBERS_COUNT {
+                    return Err(Error::UnknownChoice);
+                }
+
+                if s.get_influence_supply(s.players.me) == 0 {
+                    return Err(Error::NoInfluenceCubes);
+                }
+            }
+
+            Self::MoveInfluence(..) => {
+                Self::expect_phase(s, Phase::SenateSelection)?;
+
+                if !s.get_senate_actions().contains(self) {
+                    return Err(Error::UnknownChoice);
+                }
+            }
+
+            Self::RemoveInfluence(..) => {
+                Self::expect_phase(s, Phase::InfluenceRemovalSelection)?;
+
+                if !s.get_removal_actions().contains(self) {
+                    return Err(Error::UnknownChoice);
                 }
+            }
+
+            Self::PickConspiracy(id) => {
+                Self::expect_phase(s, Phase::ConspiracySelection)?;
+
+                if !s.interactive_units.conspiracies.contains(id) {
+                    return Err(Error::UnknownChoice);
+                }
+            }
+
+            Self::TriggerConspiracy(id) => {
+                Self::expect_phase(s, Phase::Turn)?;
+
+                if !s.me().conspiracies.contains(&Some(*id)) {
+                    return Err(Error::ConspiracyNotPrepared(*id));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expect_constructable_wonder(s: &State, wid: &wonder::Id) -> Result<(), Error> {
+        if get_constructed_wonders_count(s) >= WONDERS_CONSTRUCT_LIMIT as usize {
+            return Err(Error::WonderLimitReached);
+        }
+
+        match s.me().wonders.iter().find(|(w, _)| w == wid) {
+            None => Err(Error::WonderNotOwned(*wid)),
+            Some((_, Some(_))) => Err(Error::WonderAlreadyBuilt(*wid)),
+            _ => Ok(()),
+        }
+    }
+
+    fn expect_phase(s: &State, expected: Phase) -> Result<(), Error> {
+        if s.phase != expected {
+            return Err(Error::WrongPhase {
+                expected,
+                actual: s.phase,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn expect_seated(s: &State, p: &Nickname) -> Result<(), Error> {
+        if !s.cities.contains_key(p) {
+            return Err(Error::UnknownPlayer(*p));
+        }
+
+        Ok(())
+    }
+
+    fn expect_playable(s: &State, bid: &building::Id) -> Result<(), Error> {
+        Self::expect_phase(s, Phase::Turn)?;
+
+        if !s.buildings.playable.contains(bid) {
+            return Err(Error::BuildingNotPlayable(*bid));
+        }
+
+        Ok(())
+    }
+
+    fn expect_coins(s: &State, need: Coins) -> Result<(), Error> {
+        let have = s.me().coins;
+
+        if need > have {
+            return Err(Error::NotEnoughCoins {
+                need,
+                have,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_building_choice(s: &State, phase: Phase, bid: &building::Id) -> Result<(), Error> {
+        Self::expect_phase(s, phase)?;
+
+        if !s.interactive_units.buildings.contains(bid) {
+            return Err(Error::UnknownChoice);
+        }
+
+        Ok(())
+    }
+
+    fn validate_wonder_choice(s: &State, phase: Phase, wid: &wonder::Id) -> Result<(), Error> {
+        Self::expect_phase(s, phase)?;
+
+        if !s.interactive_units.wonders.contains(&Some(*wid)) {
+            return Err(Error::UnknownChoice);
+        }
+
+        Ok(())
+    }
+
+    fn validate_token_choice(s: &State, phase: Phase, tid: &token::Id) -> Result<(), Error> {
+        Self::expect_phase(s, phase)?;
+
+        if !s.interactive_units.tokens.contains(tid) {
+            return Err(Error::UnknownChoice);
+        }
+
+        Ok(())
+    }
+
+    fn commit(self, s: &mut State) {
+        match self {
+            Self::Prepare(v) => {
+                #[cfg(feature = "definitions")]
+                crate::definition::lock();
 
                 s.age = Age::I;
-                s.phase = Phase::WondersSelection;
+                s.set_phase(Phase::WondersSelection);
                 s.players = state::Players {
                     starts: v.p1,
                     me: v.p1,
                     enemy: v.p2,
                     fallback: None,
                 };
-                s.cities = HashMap::from([
-                    (v.p1, City::default()),
-                    (v.p2, City::default()),
-                ]);
+                s.cities = Cities::new(v.p1, v.p2);
                 s.tokens = v.board_tokens.iter().map(|id| Some(*id)).collect();
+                s.pantheon = v.pantheon.map(Pantheon::new);
+                s.agora = v.agora.map(Agora::new);
                 s.random_units = state::RandomUnits {
                     buildings: v.buildings,
                     tokens: v.random_tokens,
@@ -66,83 +419,35 @@ impl Action {
                 state::over(s, Finisher::Loser(actor), Victory::Resign);
             }
 
-            Self::SelectWhoBeginsTheNextAge(p) => {
-                if s.phase != Phase::WhoBeginsTheNextAgeSelection {
-                    return Err(Error::ActionNotAllowed);
-                }
+            Self::Timeout(loser) => {
+                state::over(s, Finisher::Loser(loser), Victory::Timeout);
+            }
 
+            Self::SelectWhoBeginsTheNextAge(p) => {
                 s.players.set_turn(p);
-                s.phase = Phase::Turn;
+                s.set_phase(Phase::Turn);
             }
 
             Self::ConstructWonder(wid, bid) => {
-                if s.phase != Phase::Turn {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if !s.buildings.playable.contains(&bid) {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                let free_wonder = s.me_mut().wonders
-                    .iter()
-                    .find(|(w, b)| w == &wid && b.is_none());
-
-                if free_wonder.is_none() {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                s.pay(PayScope::Wonders, get_wonder(&wid).cost.clone())?;
-                s.deck.pull_building(&bid);
-
-                s.me_mut().wonders.iter_mut()
-                    .for_each(|(w, b)| {
-                        if w == &wid {
-                            *b = Some(bid)
-                        }
-                    });
-
-                let total_wonders_constructed = s.me().wonders.iter()
-                    .chain(s.enemy().wonders.iter())
-                    .filter(|(_, b)| b.is_some())
-                    .count();
-
-                if total_wonders_constructed == WONDERS_CONSTRUCT_LIMIT as usize {
-                    s.me_mut().wonders
-                        .retain(|(_, b)| !b.is_none());
-
-                    s.enemy_mut().wonders
-                        .retain(|(_, b)| !b.is_none());
-                }
-
-                get_wonder(&wid).construct(s);
-
-                if s.me().tokens.contains(&token::Id::Theology) {
-                    s.play_again = true;
-                }
+                s.pay(PayScope::Wonders, get_wonder(&wid).cost.clone());
+                s.pull_building(&bid);
+                Self::construct_wonder(s, wid, bid);
 
                 after(s);
             }
 
             Self::ConstructBuilding(bid) => {
-                if s.phase != Phase::Turn {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if !s.buildings.playable.contains(&bid) {
-                    return Err(Error::ActionNotAllowed);
-                }
-
                 if s.me().chains.contains(&bid) {
                     if s.me().tokens.contains(&token::Id::Urbanism) {
-                        s.me_mut().coins += 4;
+                        s.add_coins(s.players.me, 4);
                     }
                 } else {
-                    s.pay(PayScope::from_building(&bid), get_building(&bid).cost.clone())?;
+                    s.pay(PayScope::from_building(&bid), get_building(&bid).cost.clone());
                 }
 
-                s.me_mut().buildings.push(bid);
-                s.deck.pull_building(&bid);
+                s.me_mut().buildings.insert(bid);
+                s.emit(GameEvent::BuildingConstructed { player: s.players.me, building: bid });
+                s.pull_building(&bid);
 
                 get_building(&bid).construct(s);
 
@@ -150,60 +455,29 @@ impl Action {
             }
 
             Self::DiscardBuilding(bid) => {
-                if s.phase != Phase::Turn {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if !s.buildings.playable.contains(&bid) {
-                    return Err(Error::ActionNotAllowed);
-                }
-
                 s.buildings.discarded.push(bid);
-                s.deck.pull_building(&bid);
-                s.me_mut().coins += s.me().bank.discard_reward;
+                s.emit(GameEvent::BuildingDiscarded { player: s.players.me, building: bid });
+                s.pull_building(&bid);
+                s.add_coins(s.players.me, s.me().bank.discard_reward);
 
                 after(s);
             }
 
             Self::DestructBuilding(bid) => {
-                if s.phase != Phase::DestructBuildingSelection {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if !s.interactive_units.buildings.contains(&bid) {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                s.enemy_mut().buildings.retain(|id| *id != bid);
-                get_building(&bid).destruct(s);
+                s.emit(GameEvent::BuildingDestroyed { player: s.players.enemy, building: bid });
+                Self::remove_enemy_building(s, bid);
 
                 after(s);
             }
 
             Self::PickWonder(wid) => {
-                if s.phase != Phase::WondersSelection {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                let wi = s.interactive_units.wonders.iter()
-                    .enumerate()
-                    .find_map(|(ind, val)| {
-                        if let Some(id) = val {
-                            if *id == wid {
-                                return Some(ind);
-                            }
-                        }
-
-                        None
-                    });
-
-                if let Some(ind) = wi {
-                    *s.interactive_units.wonders.get_mut(ind).unwrap() = None;
-                } else {
-                    return Err(Error::ActionNotAllowed);
-                }
+                s.interactive_units.wonders
+                    .iter_mut()
+                    .filter(|w| **w == Some(wid))
+                    .for_each(|w| *w = None);
 
                 s.me_mut().wonders.push((wid, None));
+                s.emit(GameEvent::WonderPicked { player: s.players.me, wonder: wid });
 
                 let picked_count = s.me().wonders.len() + s.enemy().wonders.len();
 
@@ -227,9 +501,10 @@ impl Action {
                     }
 
                     WONDER_TOTAL_POOL_SIZE => {
-                        s.phase = Phase::Turn;
+                        s.set_phase(Phase::Turn);
                         s.interactive_units.wonders = vec![];
                         s.deck = Deck::new(get_layout(s.age), s.random_units.buildings[&s.age].clone());
+                        s.emit(GameEvent::AgeStarted(s.age));
                         state::refresh_buildings(s);
                         state::refresh_cities(s);
                     }
@@ -238,73 +513,41 @@ impl Action {
                 }
             }
 
-            Self::PickBoardToken(tid) => {
-                return Self::pick_token(s, Phase::BoardTokenSelection, &tid);
-            }
-
-            Self::PickRandomToken(tid) => {
-                return Self::pick_token(s, Phase::RandomTokenSelection, &tid);
+            Self::PickBoardToken(tid) | Self::PickRandomToken(tid) => {
+                Self::pick_token(s, &tid);
             }
 
             Self::PickTopLineBuilding(bid) => {
-                if s.phase != Phase::TopLineBuildingSelection {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if !s.interactive_units.buildings.contains(&bid) {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                s.me_mut().buildings.push(bid);
-                s.deck.pull_building(&bid);
+                s.me_mut().buildings.insert(bid);
+                s.emit(GameEvent::BuildingConstructed { player: s.players.me, building: bid });
+                s.pull_building(&bid);
                 get_building(&bid).construct(s);
 
                 after(s);
             }
 
             Self::PickDiscardedBuilding(bid) => {
-                if s.phase != Phase::DiscardedBuildingSelection {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if !s.interactive_units.buildings.contains(&bid) {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                s.me_mut().buildings.push(bid);
+                s.me_mut().buildings.insert(bid);
                 s.buildings.discarded.retain(|id| *id != bid);
+                s.emit(GameEvent::BuildingConstructed { player: s.players.me, building: bid });
                 get_building(&bid).construct(s);
 
                 after(s);
             }
 
             Self::PickReturnedBuildings(pick, give) => {
-                if s.phase != Phase::ReturnedBuildingSelection {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if pick == give {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if !s.interactive_units.buildings.contains(&pick) {
-                    return Err(Error::ActionNotAllowed);
-                }
-
-                if !s.interactive_units.buildings.contains(&give) {
-                    return Err(Error::ActionNotAllowed);
-                }
-
                 let fallback_turn = s.players.me;
 
                 {
-                    s.me_mut().buildings.push(pick);
+                    s.me_mut().buildings.insert(pick);
+                    s.emit(GameEvent::BuildingConstructed { player: s.players.me, building: pick });
                     get_building(&pick).construct(s);
                 }
 
                 {
                     s.players.set_turn(s.players.enemy);
-                    s.me_mut().buildings.push(give);
+                    s.me_mut().buildings.insert(give);
+                    s.emit(GameEvent::BuildingConstructed { player: s.players.me, building: give });
                     get_building(&give).construct(s);
                 }
 
@@ -312,42 +555,253 @@ impl Action {
 
                 after(s);
             }
-        }
-        Ok(())
-    }
 
-    fn pick_token(s: &mut State, phase: Phase, tid: &token::Id) -> Result<(), Error> {
-        if s.phase != phase {
-            return Err(Error::ActionNotAllowed);
-        }
+            Self::PickDivinity(id, space) => {
+                let rest = s.interactive_units.divinities.iter()
+                    .filter(|d| **d != id)
+                    .copied()
+                    .collect::<Vec<_>>();
 
-        if !s.interactive_units.tokens.contains(tid) {
-            return Err(Error::ActionNotAllowed);
-        }
+                // validated, the pantheon is in play
+                let pantheon = s.pantheon.as_mut().unwrap();
+                pantheon.board.spaces[space] = Some(id);
+                pantheon.board.face_down[space] = Some(s.players.me);
+                pantheon.piles.entry(get_divinity(&id).mythology).or_default().extend(rest);
 
-        s.me_mut().tokens.push(*tid);
-        get_token(tid).construct(s);
-        let t_ind = s.tokens.iter()
-            .enumerate()
-            .find_map(|(ind, val)| {
-                if let Some(id) = val {
-                    return if id == tid {
-                        Some(ind)
-                    } else {
-                        None
-                    };
-                }
-
-                None
-            });
+                s.emit(GameEvent::DivinityPlaced { player: s.players.me, space });
 
-        *s.tokens.get_mut(t_ind.unwrap()).unwrap() = None;
+                after(s);
+            }
 
-        after(s);
-        Ok(())
-    }
-}
+            Self::ActivateDivinity(id) => {
+                let pantheon = s.pantheon.as_mut().unwrap();
 
+                match pantheon.board.find(&id) {
+                    Some(space) => {
+                        // divine theater activates for free
+                        if s.phase == Phase::Turn {
+                            Self::pay_divinity(s, space);
+                        }
+
+                        let pantheon = s.pantheon.as_mut().unwrap();
+                        pantheon.board.spaces[space] = None;
+                        pantheon.board.face_down[space] = None;
+                    }
+
+                    // chosen from the tops the gate has drawn
+                    None => {
+                        let rest = s.interactive_units.divinities.iter()
+                            .filter(|d| **d != id)
+                            .copied()
+                            .collect::<Vec<_>>();
+
+                        pantheon.return_tops(rest);
+                    }
+                }
+
+                s.me_mut().divinities.push(id);
+                s.emit(GameEvent::DivinityActivated { player: s.players.me, divinity: id });
+                get_divinity(&id).construct(s);
+
+                after(s);
+            }
+
+            Self::ActivateGate => {
+                let space = s.pantheon.as_ref().and_then(|p| p.board.gate).unwrap();
+                Self::pay_divinity(s, space);
+
+                let pantheon = s.pantheon.as_mut().unwrap();
+                pantheon.board.gate = None;
+                let divinities = pantheon.draw_tops();
+
+                s.emit(GameEvent::GateActivated { player: s.players.me });
+                s.queue_post_effect(PostEffect::ActivateDivinity(s.players.me, divinities));
+
+                after(s);
+            }
+
+            Self::ConstructDiscardedWonder(wid, bid) => {
+                s.buildings.discarded.retain(|id| *id != bid);
+                Self::construct_wonder(s, wid, bid);
+
+                after(s);
+            }
+
+            Self::DestructWonder(wid) => {
+                let wonder = s.enemy_mut().wonders.iter_mut().find(|(w, _)| *w == wid).unwrap();
+
+                if let Some(bid) = wonder.1.take() {
+                    s.buildings.discarded.push(bid);
+                }
+
+                // the wonder stays in the city unbuilt, its effects are rolled back on behalf of the owner
+                let turn = s.players.me;
+                s.players.set_turn(s.players.enemy);
+                get_wonder(&wid).destruct(s);
+                s.players.set_turn(turn);
+
+                s.emit(GameEvent::WonderDestroyed { player: s.players.enemy, wonder: wid });
+
+                after(s);
+            }
+
+            Self::StealWonder(wid) => {
+                s.enemy_mut().wonders.retain(|(w, _)| *w != wid);
+                s.me_mut().wonders.push((wid, None));
+                s.emit(GameEvent::WonderStolen { player: s.players.me, wonder: wid });
+
+                after(s);
+            }
+
+            Self::DiscardLayoutBuilding(bid) => {
+                if let Some(slot) = s.deck.buildings.iter().position(|id| *id == bid) {
+                    s.drop_slot_token(slot);
+                }
+
+                s.deck.pull_building(&bid);
+                s.buildings.discarded.push(bid);
+                s.emit(GameEvent::BuildingDiscarded { player: s.players.me, building: bid });
+
+                after(s);
+            }
+
+            Self::StealBuilding(bid) => {
+                Self::remove_enemy_building(s, bid);
+
+                s.me_mut().buildings.insert(bid);
+                s.emit(GameEvent::BuildingStolen { player: s.players.me, building: bid });
+                get_building(&bid).construct(s);
+
+                after(s);
+            }
+
+            Self::PickScientificSymbol(symbol) => {
+                Effect::Science(symbol).apply(s);
+
+                after(s);
+            }
+
+            Self::PlaceInfluence(chamber) => {
+                s.place_influence(chamber, s.players.me);
+
+                if s.phase == Phase::ConspiracySelection {
+                    // drawn conspiracies go under the pile
+                    let drawn = std::mem::take(&mut s.interactive_units.conspiracies);
+                    s.agora.as_mut().unwrap().conspiracies.extend(drawn);
+                } else {
+                    s.continue_senate(PostEffect::Senate);
+                }
+
+                after(s);
+            }
+
+            Self::MoveInfluence(from, to) => {
+                s.move_influence(from, to, s.players.me);
+                s.continue_senate(PostEffect::Senate);
+
+                after(s);
+            }
+
+            Self::RemoveInfluence(chamber) => {
+                s.remove_influence(chamber, s.players.enemy);
+                s.continue_senate(PostEffect::RemoveInfluence);
+
+                after(s);
+            }
+
+            Self::PickConspiracy(id) => {
+                let rest = std::mem::take(&mut s.interactive_units.conspiracies).into_iter()
+                    .filter(|c| *c != id)
+                    .collect::<Vec<_>>();
+
+                s.agora.as_mut().unwrap().conspiracies.extend(rest);
+                s.me_mut().conspiracies.push(Some(id));
+                s.emit(GameEvent::ConspiracyPrepared { player: s.players.me });
+
+                after(s);
+            }
+
+            Self::TriggerConspiracy(id) => {
+                let prepared = &mut s.me_mut().conspiracies;
+
+                if let Some(ind) = prepared.iter().position(|c| *c == Some(id)) {
+                    prepared.remove(ind);
+                }
+
+                s.me_mut().triggered_conspiracies.push(id);
+                s.emit(GameEvent::ConspiracyTriggered { player: s.players.me, conspiracy: id });
+                get_conspiracy(&id).construct(s);
+
+                // the move is still to be made
+                s.play_again = true;
+
+                after(s);
+            }
+        }
+    }
+
+    // effects are rolled back on behalf of the owner
+    fn remove_enemy_building(s: &mut State, bid: building::Id) {
+        let turn = s.players.me;
+
+        s.enemy_mut().buildings.remove(&bid);
+        s.players.set_turn(s.players.enemy);
+        get_building(&bid).destruct(s);
+        s.players.set_turn(turn);
+    }
+
+    // the largest offering token is spent
+    fn pay_divinity(s: &mut State, space: usize) {
+        s.take_coins(s.players.me, s.get_divinity_price(space));
+
+        let offerings = &mut s.me_mut().offerings;
+
+        if let Some(ind) = (0..offerings.len()).max_by_key(|ind| offerings[*ind]) {
+            offerings.remove(ind);
+        }
+    }
+
+    fn construct_wonder(s: &mut State, wid: wonder::Id, bid: building::Id) {
+        s.me_mut().wonders.iter_mut()
+            .for_each(|(w, b)| {
+                if w == &wid {
+                    *b = Some(bid)
+                }
+            });
+
+        s.emit(GameEvent::WonderConstructed { player: s.players.me, wonder: wid, building: bid });
+
+        if get_constructed_wonders_count(s) == WONDERS_CONSTRUCT_LIMIT as usize {
+            s.me_mut().wonders
+                .retain(|(_, b)| !b.is_none());
+
+            s.enemy_mut().wonders
+                .retain(|(_, b)| !b.is_none());
+        }
+
+        get_wonder(&wid).construct(s);
+
+        if s.me().tokens.contains(&token::Id::Theology) {
+            s.play_again = true;
+        }
+    }
+
+    fn pick_token(s: &mut State, tid: &token::Id) {
+        s.me_mut().tokens.insert(*tid);
+        s.emit(GameEvent::TokenPicked { player: s.players.me, token: *tid });
+        get_token(tid).construct(s);
+
+        // random tokens are not placed on the board
+        if let Some(slot) = s.tokens.iter_mut().find(|slot| **slot == Some(*tid)) {
+            *slot = None;
+        }
+
+        after(s);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Setup {
     pub p1: Nickname,
     pub p2: Nickname,
@@ -355,41 +809,65 @@ pub struct Setup {
     pub board_tokens: Vec<token::Id>,
     pub random_tokens: Vec<token::Id>,
     pub buildings: HashMap<Age, Vec<building::Id>>,
+    pub pantheon: Option<pantheon::Setup>,
+    pub agora: Option<agora::Setup>,
 }
 
 impl Setup {
-    pub fn new(mut p1: Nickname, mut p2: Nickname, o: crate::Options) -> Self {
-        if random() {
+    pub fn new(p1: Nickname, p2: Nickname, o: crate::Options) -> Self {
+        Self::from_seed(p1, p2, o, random())
+    }
+
+    // same seed gives the same setup on any platform, so a stored seed is enough to restore the deal
+    pub fn from_seed(mut p1: Nickname, mut p2: Nickname, o: crate::Options, seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        if rng.gen() {
             std::mem::swap(&mut p1, &mut p2);
         }
 
-        let (board_tokens, random_tokens) = Self::get_random_tokens();
+        let wonders = Self::get_random_wonders(&o, &mut rng);
+        let (board_tokens, random_tokens) = Self::get_random_tokens(&mut rng);
+        let buildings = Self::get_random_buildings(&o, &mut rng);
+        let pantheon = o.with_pantheon.then(|| Self::get_random_pantheon(&mut rng));
+        let agora = o.with_agora.then(|| Self::get_random_agora(&mut rng));
 
         Self {
             p1,
             p2,
-            wonders: Self::get_random_wonders(&o),
+            wonders,
             board_tokens,
             random_tokens,
-            buildings: Self::get_random_buildings(),
+            buildings,
+            pantheon,
+            agora,
         }
     }
 
-    pub fn get_random_wonders(o: &crate::Options) -> Vec<wonder::Id> {
-        get_all_wonders()
-            .iter()
-            .map(|(id, _)| *id)
+    pub fn get_random_wonders(o: &crate::Options, rng: &mut impl Rng) -> Vec<wonder::Id> {
+        let mut wonders = get_all_wonders()
+            .keys()
+            .copied()
             .filter(|id| {
-                o.with_promo_wonders || !wonder::Id::PROMO.contains(id)
+                (o.with_promo_wonders || !wonder::Id::PROMO.contains(id))
+                    && (o.with_pantheon || !wonder::Id::PANTHEON.contains(id))
+                    && (o.with_agora || !wonder::Id::AGORA.contains(id))
             })
-            .choose_multiple(&mut thread_rng(), WONDER_TOTAL_POOL_SIZE as usize)
+            .collect::<Vec<_>>();
+
+        Self::shuffle(&mut wonders, rng);
+        wonders.truncate(WONDER_TOTAL_POOL_SIZE as usize);
+
+        wonders
     }
 
-    pub fn get_random_tokens() -> (Vec<token::Id>, Vec<token::Id>) {
-        let tokens = get_all_tokens()
-            .iter()
-            .map(|(id, _)| *id)
-            .choose_multiple(&mut thread_rng(), get_all_tokens().len());
+    pub fn get_random_tokens(rng: &mut impl Rng) -> (Vec<token::Id>, Vec<token::Id>) {
+        let mut tokens = get_all_tokens()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+
+        Self::shuffle(&mut tokens, rng);
 
         (
             tokens.iter().take(STARTING_TOKENS_COUNT).copied().collect(),
@@ -397,23 +875,51 @@ impl Setup {
         )
     }
 
-    pub fn get_random_buildings() -> HashMap<Age, Vec<building::Id>> {
+    // grand temples replace guilds in the pantheon, agora cards replace a part of each age
+    pub fn get_random_buildings(o: &crate::Options, rng: &mut impl Rng) -> HashMap<Age, Vec<building::Id>> {
+        let last_age_kind = if o.with_pantheon {
+            building::Kind::GrandTemple
+        } else {
+            building::Kind::Guild
+        };
+
+        let agora_limit = if o.with_agora {
+            agora::AGORA_BUILDINGS_LIMIT
+        } else {
+            0
+        };
+
         let mut buildings: HashMap<Age, Vec<building::Id>> = Default::default();
 
         for age in Age::ALL {
-            let shuffled = Self::get_shuffle_buildings(age);
+            let shuffled = Self::get_shuffle_buildings(age, rng);
             match age {
                 Age::III => {
-                    let guilds = Self::get_shuffle_guilds();
-                    shuffled.iter().take((DECK_LIMIT - GUILDS_LIMIT) as usize).collect::<Vec<_>>().extend(&guilds);
+                    let mut deck = shuffled.into_iter()
+                        .take((DECK_LIMIT - GUILDS_LIMIT - agora_limit) as usize)
+                        .collect::<Vec<_>>();
+
+                    deck.extend(Self::get_shuffle_by_kind(last_age_kind, rng));
+
+                    if o.with_agora {
+                        deck.extend(Self::get_shuffle_agora(age, rng));
+                    }
+
+                    deck.shuffle(rng);
 
-                    buildings.insert(
-                        age,
-                        shuffled.into_iter().choose_multiple(&mut thread_rng(), (DECK_LIMIT + GUILDS_LIMIT) as usize),
-                    );
+                    buildings.insert(age, deck);
                 }
                 _ => {
-                    buildings.insert(age, shuffled.into_iter().take(DECK_LIMIT as usize).collect());
+                    let mut deck = shuffled.into_iter()
+                        .take((DECK_LIMIT - agora_limit) as usize)
+                        .collect::<Vec<_>>();
+
+                    if o.with_agora {
+                        deck.extend(Self::get_shuffle_agora(age, rng));
+                        deck.shuffle(rng);
+                    }
+
+                    buildings.insert(age, deck);
                 }
             };
         }
@@ -421,19 +927,599 @@ impl Setup {
         buildings
     }
 
-    fn get_shuffle_buildings(age: Age) -> Vec<building::Id> {
-        get_all_buildings()
-            .iter()
-            .filter(|(_, b)| b.age == age && b.kind != building::Kind::Guild)
-            .map(|(id, _)| *id)
-            .choose_multiple(&mut thread_rng(), get_all_buildings().len())
+    pub fn validate(&self) -> Result<(), SetupError> {
+        if self.p1 == self.p2 {
+            return Err(SetupError::SamePlayers);
+        }
+
+        if self.wonders.len() != WONDER_TOTAL_POOL_SIZE as usize {
+            return Err(SetupError::WondersCount(self.wonders.len()));
+        }
+
+        if let Some(id) = find_duplicate(self.wonders.iter()) {
+            return Err(SetupError::DuplicateWonder(id));
+        }
+
+        if let Some(id) = self.wonders.iter().find(|id| !get_all_wonders().contains_key(id)) {
+            return Err(SetupError::UnknownWonder(*id));
+        }
+
+        if self.board_tokens.len() != STARTING_TOKENS_COUNT {
+            return Err(SetupError::BoardTokensCount(self.board_tokens.len()));
+        }
+
+        if self.random_tokens.len() != RANDOM_TOKENS_COUNT {
+            return Err(SetupError::RandomTokensCount(self.random_tokens.len()));
+        }
+
+        if let Some(id) = find_duplicate(self.board_tokens.iter().chain(self.random_tokens.iter())) {
+            return Err(SetupError::DuplicateToken(id));
+        }
+
+        if let Some(id) = self.board_tokens.iter().chain(self.random_tokens.iter()).find(|id| !get_all_tokens().contains_key(id)) {
+            return Err(SetupError::UnknownToken(*id));
+        }
+
+        for age in Age::ALL {
+            let buildings = self.buildings.get(&age).map(Vec::as_slice).unwrap_or_default();
+
+            if buildings.len() != DECK_LIMIT as usize {
+                return Err(SetupError::BuildingsCount(age, buildings.len()));
+            }
+
+            // unknown ones as well, they may come from definitions installed elsewhere
+            if let Some(id) = buildings.iter().find(|id| get_all_buildings().get(id).is_none_or(|unit| unit.age != age)) {
+                return Err(SetupError::WrongAge(*id, age));
+            }
+
+            if let Some(id) = find_duplicate(buildings.iter()) {
+                return Err(SetupError::DuplicateBuilding(id));
+            }
+
+            let guilds = building::count_by_kind(buildings.iter().copied(), building::Kind::Guild);
+            let temples = building::count_by_kind(buildings.iter().copied(), building::Kind::GrandTemple);
+            let (expected_guilds, expected_temples) = match age {
+                Age::III if self.pantheon.is_some() => (0, GUILDS_LIMIT),
+                Age::III => (GUILDS_LIMIT, 0),
+                _ => (0, 0),
+            };
+
+            if guilds != expected_guilds {
+                return Err(SetupError::GuildsCount(guilds as usize));
+            }
+
+            if temples != expected_temples {
+                return Err(SetupError::TemplesCount(temples as usize));
+            }
+
+            let agora_buildings = building::count_by_kind(buildings.iter().copied(), building::Kind::Politician)
+                + building::count_by_kind(buildings.iter().copied(), building::Kind::Conspirator);
+            let expected_agora_buildings = if self.agora.is_some() {
+                agora::AGORA_BUILDINGS_LIMIT
+            } else {
+                0
+            };
+
+            if agora_buildings != expected_agora_buildings {
+                return Err(SetupError::AgoraBuildingsCount(age, agora_buildings as usize));
+            }
+        }
+
+        if let Some(p) = self.pantheon.as_ref() {
+            Self::validate_pantheon(p)?;
+        }
+
+        if let Some(a) = self.agora.as_ref() {
+            Self::validate_agora(a)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_random_pantheon(rng: &mut impl Rng) -> pantheon::Setup {
+        let mut divinities: HashMap<Mythology, Vec<divinity::Id>> = Default::default();
+
+        for m in Mythology::ALL {
+            let mut pile = divinity::filter_by_mythology(m);
+            Self::shuffle(&mut pile, rng);
+            divinities.insert(m, pile);
+        }
+
+        let mut mythology_slots = get_face_down_slots(Age::I);
+        mythology_slots.shuffle(rng);
+
+        let mut offering_slots = get_face_down_slots(Age::II);
+        offering_slots.shuffle(rng);
+
+        pantheon::Setup {
+            divinities,
+            mythology_tokens: mythology_slots.into_iter().zip(Mythology::ALL).collect(),
+            offering_tokens: offering_slots.into_iter().zip(pantheon::OFFERING_TOKENS).collect(),
+        }
+    }
+
+    fn validate_pantheon(p: &pantheon::Setup) -> Result<(), SetupError> {
+        for m in Mythology::ALL {
+            let mut pile = p.divinities.get(&m).cloned().unwrap_or_default();
+            pile.sort();
+
+            if pile != divinity::filter_by_mythology(m) {
+                return Err(SetupError::DivinityPiles);
+            }
+        }
+
+        if p.divinities.len() != Mythology::ALL.len() {
+            return Err(SetupError::DivinityPiles);
+        }
+
+        let age_i_slots = get_face_down_slots(Age::I);
+        let mut mythologies = p.mythology_tokens.values().copied().collect::<Vec<_>>();
+        mythologies.sort();
+
+        if mythologies != Mythology::ALL || p.mythology_tokens.keys().any(|slot| !age_i_slots.contains(slot)) {
+            return Err(SetupError::MythologyTokens);
+        }
+
+        let age_ii_slots = get_face_down_slots(Age::II);
+        let mut offerings = p.offering_tokens.values().copied().collect::<Vec<_>>();
+        offerings.sort();
+
+        if offerings != pantheon::OFFERING_TOKENS || p.offering_tokens.keys().any(|slot| !age_ii_slots.contains(slot)) {
+            return Err(SetupError::OfferingTokens);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_random_agora(rng: &mut impl Rng) -> agora::Setup {
+        let mut decrees = get_all_decrees().keys().copied().collect::<Vec<_>>();
+        Self::shuffle(&mut decrees, rng);
+        decrees.truncate(agora::CHAMBERS_COUNT);
+
+        let mut conspiracies = get_all_conspiracies().keys().copied().collect::<Vec<_>>();
+        Self::shuffle(&mut conspiracies, rng);
+
+        agora::Setup {
+            decrees,
+            conspiracies,
+        }
+    }
+
+    fn validate_agora(a: &agora::Setup) -> Result<(), SetupError> {
+        if a.decrees.len() != agora::CHAMBERS_COUNT || find_duplicate(a.decrees.iter()).is_some() {
+            return Err(SetupError::Decrees);
+        }
+
+        let mut conspiracies = a.conspiracies.clone();
+        conspiracies.sort();
+
+        let mut expected = get_all_conspiracies().keys().copied().collect::<Vec<conspiracy::Id>>();
+        expected.sort();
+
+        if conspiracies != expected {
+            return Err(SetupError::Conspiracies);
+        }
+
+        Ok(())
+    }
+
+    fn get_shuffle_buildings(age: Age, rng: &mut impl Rng) -> Vec<building::Id> {
+        let mut buildings = get_all_buildings()
+            .values()
+            .filter(|b| b.age == age && b.kind != building::Kind::Guild && !b.kind.is_expansion())
+            .map(|b| b.id)
+            .collect::<Vec<_>>();
+
+        Self::shuffle(&mut buildings, rng);
+
+        buildings
+    }
+
+    fn get_shuffle_by_kind(kind: building::Kind, rng: &mut impl Rng) -> Vec<building::Id> {
+        let mut buildings = get_all_buildings()
+            .values()
+            .filter(|b| b.kind == kind)
+            .map(|b| b.id)
+            .collect::<Vec<_>>();
+
+        Self::shuffle(&mut buildings, rng);
+        buildings.truncate(GUILDS_LIMIT as usize);
+
+        buildings
+    }
+
+    fn get_shuffle_agora(age: Age, rng: &mut impl Rng) -> Vec<building::Id> {
+        let mut buildings = get_all_buildings()
+            .values()
+            .filter(|b| b.age == age && matches!(b.kind, building::Kind::Politician | building::Kind::Conspirator))
+            .map(|b| b.id)
+            .collect::<Vec<_>>();
+
+        Self::shuffle(&mut buildings, rng);
+        buildings.truncate(agora::AGORA_BUILDINGS_LIMIT as usize);
+
+        buildings
+    }
+
+    // registries are hash maps, sort first to not depend on their iteration order
+    fn shuffle<T: Ord>(units: &mut [T], rng: &mut impl Rng) {
+        units.sort();
+        units.shuffle(rng);
+    }
+}
+
+fn find_duplicate<'a, T: Eq + Hash + Copy + 'a>(mut units: impl Iterator<Item = &'a T>) -> Option<T> {
+    let mut seen = HashSet::new();
+
+    units.find(|id| !seen.insert(*id)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::game_11_actions;
+
+    fn get_candidates() -> Vec<Action> {
+        let mut buildings = get_all_buildings().keys().copied().collect::<Vec<_>>();
+        let mut wonders = get_all_wonders().keys().copied().collect::<Vec<_>>();
+        let mut tokens = get_all_tokens().keys().copied().collect::<Vec<_>>();
+        buildings.sort();
+        wonders.sort();
+        tokens.sort();
+
+        let mut actions = vec![
+            game_11_actions().remove(0),
+            Action::SelectWhoBeginsTheNextAge(1),
+            Action::SelectWhoBeginsTheNextAge(2),
+        ];
+
+        for bid in buildings.iter().copied() {
+            actions.push(Action::ConstructBuilding(bid));
+            actions.push(Action::DiscardBuilding(bid));
+            actions.push(Action::DestructBuilding(bid));
+            actions.push(Action::PickTopLineBuilding(bid));
+            actions.push(Action::PickDiscardedBuilding(bid));
+
+            for wid in wonders.iter().copied() {
+                actions.push(Action::ConstructWonder(wid, bid));
+            }
+        }
+
+        for wid in wonders.iter().copied() {
+            actions.push(Action::PickWonder(wid));
+        }
+
+        for tid in tokens.iter().copied() {
+            actions.push(Action::PickBoardToken(tid));
+            actions.push(Action::PickRandomToken(tid));
+        }
+
+        actions
+    }
+
+    fn get_returned_candidates(s: &State) -> Vec<Action> {
+        let mut buildings = s.interactive_units.buildings.clone();
+        buildings.push(building::Id::LumberYard);
+
+        buildings.iter()
+            .flat_map(|pick| {
+                buildings.iter().map(|give| Action::PickReturnedBuildings(*pick, *give))
+            })
+            .collect()
     }
 
-    fn get_shuffle_guilds() -> Vec<building::Id> {
-        get_all_buildings()
-            .iter()
-            .filter(|(_, b)| b.kind == building::Kind::Guild)
-            .map(|(id, _)| *id)
-            .choose_multiple(&mut thread_rng(), GUILDS_LIMIT as usize)
+    #[test]
+    fn setup_from_seed() {
+        let setup = Setup::from_seed(1, 2, Default::default(), 42);
+
+        assert_eq!(setup, Setup::from_seed(1, 2, Default::default(), 42));
+        assert_ne!(setup, Setup::from_seed(1, 2, Default::default(), 43));
+
+        // pinned to catch changes in the deal algorithm
+        assert_eq!(
+            vec![
+                wonder::Id::TheColossus,
+                wonder::Id::TheGreatLighthouse,
+                wonder::Id::TheAppianWay,
+                wonder::Id::TheMausoleum,
+                wonder::Id::TheTempleOfArtemis,
+                wonder::Id::TheStatueOfZeus,
+                wonder::Id::Piraeus,
+                wonder::Id::TheHangingGardens,
+            ],
+            setup.wonders,
+        );
+        assert_eq!(
+            vec![
+                token::Id::Agriculture,
+                token::Id::Architecture,
+                token::Id::Philosophy,
+                token::Id::Mathematics,
+                token::Id::Strategy,
+            ],
+            setup.board_tokens,
+        );
+        assert_eq!(
+            vec![
+                building::Id::Scriptorium,
+                building::Id::Baths,
+                building::Id::Garrison,
+                building::Id::WoodReserve,
+                building::Id::ClayReserve,
+            ],
+            setup.buildings[&Age::I][..5],
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn random_setup_is_valid() {
+        for seed in 0..100 {
+            let o = crate::Options {
+                with_promo_wonders: seed % 2 == 0,
+                with_pantheon: seed % 3 == 0,
+                with_agora: seed % 5 < 2,
+            };
+            let setup = Setup::from_seed(1, 2, o, seed);
+            let kind = if o.with_pantheon { building::Kind::GrandTemple } else { building::Kind::Guild };
+
+            assert_eq!(Ok(()), setup.validate());
+            assert_eq!(3, building::count_by_kind(setup.buildings[&Age::III].iter().copied(), kind));
+            assert_eq!(o.with_pantheon, setup.pantheon.is_some());
+            assert_eq!(o.with_agora, setup.agora.is_some());
+        }
+    }
+
+    #[test]
+    fn great_library_offers_three_tokens() {
+        let setup = Setup::from_seed(1, 2, Default::default(), 7);
+        assert_eq!(3, setup.random_tokens.len());
+
+        let mut s = State::default();
+        Action::Prepare(setup.clone()).apply(&mut s).expect("its ok");
+        Effect::PickRandomToken.apply(&mut s);
+
+        assert_eq!(
+            Some(&PostEffect::PickRandomToken(s.players.me, setup.random_tokens)),
+            s.post_effects.last()
+        );
+    }
+
+    #[test]
+    fn setup_validation() {
+        let Action::Prepare(valid) = game_11_actions().remove(0) else {
+            unreachable!()
+        };
+
+        assert_eq!(Ok(()), valid.validate());
+
+        let check = |expected: SetupError, f: fn(&mut Setup)| {
+            let mut setup = valid.clone();
+            f(&mut setup);
+
+            assert_eq!(Err(expected.clone()), setup.validate());
+            assert_eq!(Err(Error::InvalidSetup(expected)), Action::Prepare(setup).apply(&mut State::default()));
+        };
+
+        check(SetupError::SamePlayers, |s| s.p2 = s.p1);
+        check(SetupError::WondersCount(7), |s| { s.wonders.pop(); });
+        check(SetupError::DuplicateWonder(wonder::Id::TheHangingGardens), |s| s.wonders[7] = s.wonders[0]);
+        check(SetupError::BoardTokensCount(6), |s| s.board_tokens.push(token::Id::Mathematics));
+        check(SetupError::RandomTokensCount(2), |s| { s.random_tokens.pop(); });
+        check(SetupError::DuplicateToken(token::Id::Economy), |s| s.random_tokens[0] = token::Id::Economy);
+        check(SetupError::BuildingsCount(Age::II, 0), |s| { s.buildings.remove(&Age::II); });
+        check(SetupError::BuildingsCount(Age::I, 21), |s| s.buildings.get_mut(&Age::I).unwrap().push(building::Id::Press));
+        check(SetupError::WrongAge(building::Id::Forum, Age::I), |s| s.buildings.get_mut(&Age::I).unwrap()[0] = building::Id::Forum);
+        check(SetupError::DuplicateBuilding(building::Id::Theater), |s| s.buildings.get_mut(&Age::I).unwrap()[0] = building::Id::Theater);
+        check(SetupError::GuildsCount(2), |s| s.buildings.get_mut(&Age::III).unwrap()[8] = building::Id::Study);
+    }
+
+    #[test]
+    fn errors() {
+        let actions = game_11_actions();
+        let check = |ply: usize, action: Action, expected: Error| {
+            let mut s = State::from(actions[..ply].to_vec()).expect("its ok");
+
+            assert_eq!(Err(expected), action.apply(&mut s));
+        };
+
+        check(1, actions[0].clone(), Error::WrongPhase { expected: Phase::None, actual: Phase::WondersSelection });
+        check(0, Action::Resign(1), Error::GameNotStarted);
+        check(0, Action::Timeout(1), Error::GameNotStarted);
+        check(1, Action::PickWonder(wonder::Id::Piraeus), Error::UnknownChoice);
+        check(9, Action::PickWonder(wonder::Id::ThePyramids), Error::WrongPhase { expected: Phase::WondersSelection, actual: Phase::Turn });
+        check(9, Action::ConstructBuilding(building::Id::Theater), Error::BuildingNotPlayable(building::Id::Theater));
+        check(9, Action::ConstructWonder(wonder::Id::TheColossus, building::Id::WoodReserve), Error::WonderNotOwned(wonder::Id::TheColossus));
+        check(9, Action::ConstructWonder(wonder::Id::TheTempleOfArtemis, building::Id::WoodReserve), Error::NotEnoughCoins { need: 8, have: 7 });
+        check(42, Action::ConstructWonder(wonder::Id::Messe, building::Id::Brewery), Error::WrongPhase { expected: Phase::Turn, actual: Phase::TopLineBuildingSelection });
+        check(44, Action::ConstructWonder(wonder::Id::Messe, building::Id::Laboratory), Error::WonderAlreadyBuilt(wonder::Id::Messe));
+        check(65, Action::PickReturnedBuildings(building::Id::Study, building::Id::Study), Error::UnknownChoice);
+        check(actions.len(), Action::Resign(1), Error::GameOver);
+
+        // 7 wonders are built, the last one is left to the player on turn
+        let mut s = State::from(actions[..67].to_vec()).expect("its ok");
+        s.me_mut().wonders = vec![
+            (wonder::Id::TheSphinx, None),
+            (wonder::Id::TheColossus, Some(building::Id::Theater)),
+            (wonder::Id::TheMausoleum, Some(building::Id::Theater)),
+            (wonder::Id::ThePyramids, Some(building::Id::Theater)),
+        ];
+        s.enemy_mut().wonders = vec![
+            (wonder::Id::TheHangingGardens, Some(building::Id::Theater)),
+            (wonder::Id::TheTempleOfArtemis, Some(building::Id::Theater)),
+            (wonder::Id::Messe, Some(building::Id::Theater)),
+            (wonder::Id::StatueOfLiberty, Some(building::Id::Theater)),
+        ];
+
+        assert_eq!(Err(Error::WonderLimitReached), Action::ConstructWonder(wonder::Id::TheSphinx, building::Id::Obelisk).apply(&mut s));
+    }
+
+    #[test]
+    fn actor() {
+        let actions = game_11_actions();
+        let mut s = State::default();
+
+        assert_eq!(Err(Error::UnknownPlayer(1)), actions[0].clone().apply_by(&mut s, 1));
+
+        // second age starts with the selection by player 1
+        let mut s = State::from(actions[..29].to_vec()).expect("its ok");
+
+        assert_eq!(Err(Error::UnknownPlayer(3)), Action::SelectWhoBeginsTheNextAge(1).apply_by(&mut s, 3));
+        assert_eq!(Err(Error::NotYourTurn), Action::SelectWhoBeginsTheNextAge(1).apply_by(&mut s, 2));
+        assert_eq!(Err(Error::UnknownPlayer(3)), Action::SelectWhoBeginsTheNextAge(3).apply_by(&mut s, 1));
+        assert_eq!(Err(Error::UnknownPlayer(3)), Action::Resign(3).apply(&mut s));
+        assert_eq!(Err(Error::UnknownPlayer(3)), Action::Timeout(3).apply(&mut s));
+        assert_eq!(Err(Error::NotYourTurn), Action::Resign(1).apply_by(&mut s, 2));
+        assert_eq!(Ok(vec![GameEvent::PhaseChanged(Phase::Turn)]), Action::SelectWhoBeginsTheNextAge(1).apply_by(&mut s, 1));
+
+        assert_eq!(Err(Error::NotYourTurn), Action::DiscardBuilding(building::Id::DryingRoom).apply_by(&mut s, 2));
+        assert!(Action::Resign(2).apply_by(&mut s, 2).is_ok());
+        assert_eq!(Some(1), s.finish.map(|f| f.winner));
+    }
+
+    #[test]
+    fn rejected_actions_keep_state() {
+        let candidates = get_candidates();
+        let mut actions = game_11_actions().into_iter();
+        let mut s = State::default();
+
+        actions.next().unwrap().apply(&mut s).expect("its ok");
+
+        for action in actions {
+            let legal = s.legal_actions();
+            let before = s.clone();
+
+            for candidate in candidates.iter().chain(get_returned_candidates(&s).iter()) {
+                if legal.contains(candidate) {
+                    let mut next = s.clone();
+                    assert!(candidate.clone().apply(&mut next).is_ok(), "{:?} is rejected", candidate);
+                } else {
+                    assert!(candidate.clone().apply(&mut s).is_err(), "{:?} is applied", candidate);
+                    assert_eq!(before, s, "{:?} changed state", candidate);
+                }
+            }
+
+            action.apply(&mut s).expect("its ok");
+        }
+
+        let before = s.clone();
+
+        assert!(Action::Resign(1).apply(&mut s).is_err());
+        assert_eq!(before, s);
+    }
+
+    #[test]
+    fn destruction_reverses_construction() {
+        let actions = game_11_actions();
+        let destroyers = [
+            (wonder::Id::TheStatueOfZeus, building::Kind::RawMaterials),
+            (wonder::Id::CircusMaximus, building::Kind::ManufacturedGoods),
+        ];
+        let mut checked = 0;
+
+        for len in (10..=70).step_by(6) {
+            let s = State::from(actions[..len].to_vec()).expect("its ok");
+            let (me, owner) = (s.players.me, s.players.enemy);
+            let Some(under) = s.buildings.playable.iter().min().copied() else {
+                continue;
+            };
+
+            if s.phase != Phase::Turn {
+                continue;
+            }
+
+            for (wid, kind) in destroyers {
+                if s.cities.values().any(|c| c.wonders.iter().any(|(w, _)| *w == wid)) {
+                    continue;
+                }
+
+                let mut buildings = building::filter_by_kind(get_all_buildings().keys().copied(), kind);
+                buildings.retain(|bid| s.cities.values().all(|c| !c.buildings.contains(bid)));
+                buildings.sort();
+
+                for bid in buildings {
+                    // the owner has the building, the player on turn a wonder to destroy it
+                    let mut next = s.clone();
+                    next.players.set_turn(owner);
+                    next.me_mut().buildings.insert(bid);
+                    get_building(&bid).construct(&mut next);
+                    next.players.set_turn(me);
+                    next.me_mut().wonders.push((wid, None));
+                    next.me_mut().coins = 50;
+                    state::refresh_cities(&mut next);
+
+                    assert_ne!(s.cities[&owner].resources, next.cities[&owner].resources);
+                    let built = next.clone();
+
+                    Action::ConstructWonder(wid, under).apply(&mut next).expect("its ok");
+                    assert_eq!(Phase::DestructBuildingSelection, next.phase, "{:?} after {} actions", bid, len);
+                    assert!(next.legal_actions().contains(&Action::DestructBuilding(bid)));
+                    Action::DestructBuilding(bid).apply(&mut next).expect("its ok");
+
+                    for p in [me, owner] {
+                        let (before, after) = (&s.cities[&p], &next.cities[&p]);
+                        let context = format!("{:?} of {} after {} actions", bid, p, len);
+
+                        assert_eq!(before.resources, after.resources, "{}", context);
+                        assert_eq!(before.chains, after.chains, "{}", context);
+                        assert_eq!(before.bank.resource_price, after.bank.resource_price, "{}", context);
+                        assert_eq!(before.bank.discounts, after.bank.discounts, "{}", context);
+                        assert_eq!(before.bank.discard_reward, after.bank.discard_reward, "{}", context);
+                    }
+
+                    // the destroyer paid more for the resource while the owner had it
+                    let fixed = |r| state::get_fixed_price_source(&built, r).is_some();
+                    let resource = Resource::ALL.into_iter()
+                        .find(|r| built.cities[&owner].resources[r] != s.cities[&owner].resources[r])
+                        .unwrap();
+
+                    if !fixed(resource) {
+                        assert!(built.me().bank.resource_price[&resource] > s.me().bank.resource_price[&resource]);
+                    }
+
+                    checked += 1;
+                }
+            }
+        }
+
+        assert!(checked > 20, "only {} destructions", checked);
+    }
+
+    #[test]
+    fn wonder_destruction_reverses_construction() {
+        let s = State::from(game_11_actions()[..20].to_vec()).expect("its ok");
+        let (me, owner) = (s.players.me, s.players.enemy);
+
+        for wid in [wonder::Id::TheGreatLighthouse, wonder::Id::Piraeus, wonder::Id::Sanctuary] {
+            let construct = |s: &mut State| {
+                s.players.set_turn(owner);
+                Action::construct_wonder(s, wid, building::Id::Theater);
+                s.players.set_turn(me);
+                s.play_again = false;
+            };
+
+            let mut next = s.clone();
+            next.players.set_turn(owner);
+            next.me_mut().wonders.push((wid, None));
+            construct(&mut next);
+
+            let built = next.clone();
+            assert_ne!(s.cities[&owner].bank, built.cities[&owner].bank);
+
+            Effect::DestructWonder.apply(&mut next);
+            next.post_effects.remove(0).apply(&mut next);
+            Action::DestructWonder(wid).apply(&mut next).expect("its ok");
+
+            let (before, after) = (&s.cities[&owner].bank, &next.cities[&owner].bank);
+            assert_eq!(before.discounts, after.discounts, "{:?}", wid);
+            assert_eq!(before.divinity_discount, after.divinity_discount, "{:?}", wid);
+            assert_eq!(before.resource_price, after.resource_price, "{:?}", wid);
+            assert_eq!(s.cities[&owner].resources, next.cities[&owner].resources, "{:?}", wid);
+            assert_eq!(Some(&building::Id::Theater), next.buildings.discarded.last());
+
+            // a rebuilt wonder counts its effects once
+            construct(&mut next);
+            assert_eq!(built.cities[&owner].bank.discounts, next.cities[&owner].bank.discounts, "{:?}", wid);
+            assert_eq!(built.cities[&owner].bank.divinity_discount, next.cities[&owner].bank.divinity_discount, "{:?}", wid);
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Hints for the player on turn: legal moves ranked by a one move look ahead.
+//!
+//! Reasons only use what both players can see: prices, chains, science symbols and the
+//! conflict track.
+use crate::{
+    prelude::*,
+    building,
+    event::GameEvent,
+    game::Game,
+    token,
+    wonder,
+};
+
+// a win at once is above any difference in points
+const VICTORY: i32 = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub action: Action,
+    // score margin of the player on turn after the move
+    pub score: i32,
+    pub reasons: Vec<Reason>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    // coins to pay with the current prices of the player
+    Cost(Coins),
+    // free construction by the chain of an owned building
+    Chain(building::Id),
+    // the cheapest of the wonders the player can afford
+    CheapestWonder(wonder::Id),
+    PlayAgain,
+    // coins for a discarded building
+    Discard(Coins),
+    // second symbol of a kind, a progress token to pick
+    ScienceToken(ScientificSymbol),
+    // the opponent would pair the symbol or come one symbol short of supremacy
+    DeniesScience(ScientificSymbol),
+    // the conflict pawn enters a zone which takes coins from the opponent
+    MilitaryFine(Coins),
+    Victory(Victory),
+}
+
+/// Legal moves of the player on turn, the best first.
+pub fn suggest(game: &Game) -> Vec<Suggestion> {
+    rank(game.state())
+}
+
+pub(crate) fn rank(s: &State) -> Vec<Suggestion> {
+    let me = s.players.me;
+    let legal = s.legal_actions();
+    let cheapest_wonder = get_cheapest_wonder(s, &legal);
+
+    let mut suggestions = legal.into_iter()
+        .map(|action| {
+            let mut next = s.clone();
+            let events = action.clone().apply(&mut next).expect("legal move is accepted");
+            let mut reasons = get_static_reasons(s, &action);
+
+            if let Action::ConstructWonder(wid, _) = &action {
+                if Some(*wid) == cheapest_wonder {
+                    reasons.push(Reason::CheapestWonder(*wid));
+                }
+            }
+
+            reasons.extend(get_event_reasons(s, &events));
+
+            Suggestion {
+                score: evaluate(&next, me),
+                action,
+                reasons,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // stable, equal moves keep the order of legal actions
+    suggestions.sort_by_key(|suggestion| -suggestion.score);
+
+    suggestions
+}
+
+fn get_static_reasons(s: &State, action: &Action) -> Vec<Reason> {
+    let mut reasons = vec![];
+
+    match action {
+        Action::ConstructBuilding(bid) => {
+            let price = if s.me().chains.contains(bid) {
+                0
+            } else {
+                s.get_price(PayScope::from_building(bid), get_building(bid).cost.clone())
+            };
+
+            reasons.push(Reason::Cost(price));
+
+            if let Some(from) = get_chain_source(s.me(), bid) {
+                reasons.push(Reason::Chain(from));
+            }
+
+            reasons.extend(get_denied_symbol(s, bid).map(Reason::DeniesScience));
+        }
+
+        Action::ConstructWonder(wid, bid) => {
+            reasons.push(Reason::Cost(s.get_price(PayScope::Wonders, get_wonder(wid).cost.clone())));
+
+            if get_wonder(wid).effects.contains(&Effect::PlayAgain) || s.me().tokens.contains(&token::Id::Theology) {
+                reasons.push(Reason::PlayAgain);
+            }
+
+            reasons.extend(get_denied_symbol(s, bid).map(Reason::DeniesScience));
+        }
+
+        Action::DiscardBuilding(bid) => {
+            reasons.push(Reason::Discard(s.me().bank.discard_reward));
+            reasons.extend(get_denied_symbol(s, bid).map(Reason::DeniesScience));
+        }
+
+        _ => (),
+    }
+
+    reasons
+}
+
+// drawn units are never exposed by events, so they are safe to look at
+fn get_event_reasons(s: &State, events: &[GameEvent]) -> Vec<Reason> {
+    events.iter()
+        .filter_map(|event| match event {
+            GameEvent::MilitaryFine { player, coins } if *player == s.players.enemy && *coins > 0 => {
+                Some(Reason::MilitaryFine(*coins))
+            }
+
+            GameEvent::ScienceSymbolGained { player, symbol } if *player == s.players.me => {
+                let owned = s.me().scientific_symbols.iter().any(|(owned, count)| owned == symbol && *count == 1);
+
+                owned.then_some(Reason::ScienceToken(*symbol))
+            }
+
+            GameEvent::GameFinished(finish) if finish.winner == s.players.me => {
+                Some(Reason::Victory(finish.victory))
+            }
+
+            _ => None,
+        })
+        .collect()
+}
+
+fn get_chain_source(city: &City, bid: &building::Id) -> Option<building::Id> {
+    city.buildings.iter().find(|from| get_building(from).effects.contains(&Effect::Chain(*bid)))
+}
+
+// science symbol of the building the opponent is close to make use of
+fn get_denied_symbol(s: &State, bid: &building::Id) -> Option<ScientificSymbol> {
+    let symbol = get_building(bid).effects.iter()
+        .find_map(|effect| match effect {
+            Effect::Science(symbol) => Some(*symbol),
+            _ => None,
+        })?;
+
+    let symbols = &s.enemy().scientific_symbols;
+    // a second copy makes the pair, a third one gives nothing
+    let pairs = symbols.iter().any(|(owned, count)| *owned == symbol && *count == 1);
+    // a new symbol brings the supremacy closer, an owned one does not
+    let closes_in = symbols.len() + 2 >= DIFFERENT_SCIENTIFIC_SYMBOLS_FOR_SUPREMACY as usize
+        && !symbols.iter().any(|(owned, _)| *owned == symbol);
+
+    (pairs || closes_in).then_some(symbol)
+}
+
+fn get_cheapest_wonder(s: &State, legal: &[Action]) -> Option<wonder::Id> {
+    let mut wonders = legal.iter()
+        .filter_map(|action| match action {
+            Action::ConstructWonder(wid, _) => Some(*wid),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    wonders.sort();
+    wonders.dedup();
+
+    // a single wonder is not a choice
+    if wonders.len() < 2 {
+        return None;
+    }
+
+    wonders.into_iter().min_by_key(|wid| s.get_price(PayScope::Wonders, get_wonder(wid).cost.clone()))
+}
+
+fn evaluate(s: &State, player: Nickname) -> i32 {
+    let enemy = if s.players.me == player { s.players.enemy } else { s.players.me };
+
+    if let Some(finish) = &s.finish {
+        return if finish.winner == player { VICTORY } else { -VICTORY };
+    }
+
+    s.cities[&player].score.total as i32 - s.cities[&enemy].score.total as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        building::Id::*,
+        tests::game_11_actions,
+    };
+
+    #[test]
+    fn explains_moves() {
+        let actions = game_11_actions();
+        let reasons = |len: usize, action: Action| {
+            let s = State::from(actions[..len].to_vec()).expect("its ok");
+            let suggestions = rank(&s);
+
+            assert_eq!(s.legal_actions().len(), suggestions.len());
+            assert!(suggestions.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+            suggestions.into_iter()
+                .find(|suggestion| suggestion.action == action)
+                .map(|suggestion| suggestion.reasons)
+                .unwrap()
+        };
+
+        assert_eq!(
+            vec![Reason::Cost(0), Reason::Chain(Scriptorium), Reason::ScienceToken(ScientificSymbol::Writing)],
+            reasons(36, Action::ConstructBuilding(Library)),
+        );
+        assert_eq!(
+            vec![Reason::Cost(0), Reason::Chain(Stable), Reason::MilitaryFine(5)],
+            reasons(40, Action::ConstructBuilding(HorseBreeders)),
+        );
+        assert_eq!(
+            vec![Reason::Cost(2), Reason::DeniesScience(ScientificSymbol::Astrology)],
+            reasons(72, Action::ConstructBuilding(Observatory)),
+        );
+        assert!(reasons(75, Action::DiscardBuilding(Academy)).contains(&Reason::Victory(Victory::Civilian)));
+    }
+
+    #[test]
+    fn denies_unpaired_symbols() {
+        let mut s = State::from(game_11_actions()[..72].to_vec()).expect("its ok");
+
+        s.enemy_mut().scientific_symbols = vec![(ScientificSymbol::Astrology, 1)];
+        assert_eq!(Some(ScientificSymbol::Astrology), get_denied_symbol(&s, &Observatory));
+
+        s.enemy_mut().scientific_symbols = vec![(ScientificSymbol::Astrology, 2)];
+        assert_eq!(None, get_denied_symbol(&s, &Observatory));
+
+        // four symbols are close to the supremacy, but only a new one brings it closer
+        s.enemy_mut().scientific_symbols = vec![
+            (ScientificSymbol::Astrology, 2),
+            (ScientificSymbol::Wheel, 1),
+            (ScientificSymbol::Sundial, 1),
+            (ScientificSymbol::Mortar, 1),
+        ];
+        assert_eq!(None, get_denied_symbol(&s, &Observatory));
+
+        s.enemy_mut().scientific_symbols[0].0 = ScientificSymbol::Compass;
+        assert_eq!(Some(ScientificSymbol::Astrology), get_denied_symbol(&s, &Observatory));
+    }
+}
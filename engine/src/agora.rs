@@ -0,0 +1,455 @@
+//! Senate of the Agora expansion: chambers, influence cubes, decrees and conspiracies.
+//!
+//! The rules are those of the expansion, the card content is not yet: politicians, conspirators,
+//! conspiracies, decrees and the Curia Julia and Knossos wonders are provisional units built from
+//! the existing effects until the printed texts are transcribed. Their ids and effects may change
+//! without a notice.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use crate::{
+    prelude::*,
+    conspiracy,
+    decree,
+    military::{ConflictTrack, Zone},
+    player::Finisher,
+    state,
+};
+
+pub const CHAMBERS_COUNT: usize = 6;
+pub const INFLUENCE_CUBES: u8 = 12;
+pub const CONSPIRACIES_DRAW_COUNT: usize = 2;
+// politicians and conspirators dealt into each age deck instead of base buildings
+pub const AGORA_BUILDINGS_LIMIT: u8 = 3;
+// chambers getting their decree face down, every other one starting from the second
+pub const FACE_DOWN_CHAMBERS: [usize; 3] = [1, 3, 5];
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chamber {
+    // none while face down
+    pub decree: Option<decree::Id>,
+    pub influence: HashMap<Nickname, u8>,
+    // player with more cubes, the decree works for this player only
+    pub controller: Option<Nickname>,
+}
+
+impl Chamber {
+    pub fn new(decree: Option<decree::Id>) -> Self {
+        Self {
+            decree,
+            influence: Default::default(),
+            controller: None,
+        }
+    }
+
+    pub fn influence_of(&self, player: Nickname) -> u8 {
+        self.influence.get(&player).copied().unwrap_or_default()
+    }
+
+    fn get_controller(&self) -> Option<Nickname> {
+        let mut influence = self.influence.iter()
+            .filter(|(_, cubes)| **cubes > 0)
+            .map(|(p, cubes)| (*p, *cubes))
+            .collect::<Vec<_>>();
+        influence.sort_by_key(|(_, cubes)| Reverse(*cubes));
+
+        match influence[..] {
+            [(p, _)] => Some(p),
+            [(p, first), (_, second), ..] if first > second => Some(p),
+            _ => None,
+        }
+    }
+}
+
+/// Public part of the Agora expansion: the senate chambers with decrees and influence.
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Board {
+    pub chambers: Vec<Chamber>,
+}
+
+impl Board {
+    pub fn influence_of(&self, player: Nickname) -> u8 {
+        self.chambers.iter().map(|c| c.influence_of(player)).sum()
+    }
+
+    pub fn controlled_by(&self, player: Nickname) -> Vec<usize> {
+        (0..self.chambers.len())
+            .filter(|ind| self.chambers[*ind].controller == Some(player))
+            .collect()
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Agora {
+    pub board: Board,
+    // left in the current senate selection
+    pub actions: u8,
+
+    // clients invisible
+    pub conspiracies: Vec<conspiracy::Id>,
+    // chamber -> face down decree, revealed once the chamber gets a controller
+    pub decrees: HashMap<usize, decree::Id>,
+}
+
+impl Agora {
+    pub fn new(setup: Setup) -> Self {
+        let mut decrees = HashMap::new();
+        let chambers = setup.decrees.into_iter()
+            .enumerate()
+            .map(|(ind, decree)| {
+                if FACE_DOWN_CHAMBERS.contains(&ind) {
+                    decrees.insert(ind, decree);
+                    Chamber::new(None)
+                } else {
+                    Chamber::new(Some(decree))
+                }
+            })
+            .collect();
+
+        Self {
+            board: Board {
+                chambers,
+            },
+            actions: 0,
+            conspiracies: setup.conspiracies,
+            decrees,
+        }
+    }
+
+    pub fn draw(&mut self) -> Vec<conspiracy::Id> {
+        let count = self.conspiracies.len().min(CONSPIRACIES_DRAW_COUNT);
+
+        self.conspiracies.drain(..count).collect()
+    }
+}
+
+/// Base track where zones give senate actions instead of fines.
+#[derive(Debug)]
+pub struct InfluenceTrack;
+
+impl ConflictTrack for InfluenceTrack {
+    fn zones(&self) -> &[Zone] {
+        &INFLUENCE_ZONES
+    }
+}
+
+static INFLUENCE_ZONES: LazyLock<Vec<Zone>> = LazyLock::new(|| {
+    vec![
+        Zone {
+            start: 0,
+            points: 0,
+            effects: vec![],
+        },
+        Zone {
+            start: 1,
+            points: 2,
+            effects: vec![],
+        },
+        Zone {
+            start: 3,
+            points: 5,
+            effects: vec![
+                Effect::Senate(1),
+            ],
+        },
+        Zone {
+            start: 6,
+            points: 10,
+            effects: vec![
+                Effect::Senate(2),
+            ],
+        },
+    ]
+});
+
+/// Deal of the Agora expansion, part of [`crate::Setup`].
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Setup {
+    // decree of each chamber, left to right
+    pub decrees: Vec<decree::Id>,
+    // draw pile, top first
+    pub conspiracies: Vec<conspiracy::Id>,
+}
+
+impl State {
+    pub fn get_influence_supply(&self, player: Nickname) -> u8 {
+        let placed = self.agora.as_ref()
+            .map(|a| a.board.influence_of(player))
+            .unwrap_or_default();
+
+        INFLUENCE_CUBES.saturating_sub(placed)
+    }
+
+    pub(crate) fn place_influence(&mut self, chamber: usize, player: Nickname) {
+        let agora = self.agora.as_mut().unwrap();
+        *agora.board.chambers[chamber].influence.entry(player).or_default() += 1;
+
+        self.emit(GameEvent::InfluencePlaced { player, chamber });
+        self.refresh_chamber(chamber);
+    }
+
+    pub(crate) fn remove_influence(&mut self, chamber: usize, player: Nickname) {
+        let agora = self.agora.as_mut().unwrap();
+
+        if let Some(cubes) = agora.board.chambers[chamber].influence.get_mut(&player) {
+            *cubes = cubes.saturating_sub(1);
+        }
+
+        self.emit(GameEvent::InfluenceRemoved { player, chamber });
+        self.refresh_chamber(chamber);
+    }
+
+    pub(crate) fn move_influence(&mut self, from: usize, to: usize, player: Nickname) {
+        let chambers = &mut self.agora.as_mut().unwrap().board.chambers;
+
+        if let Some(cubes) = chambers[from].influence.get_mut(&player) {
+            *cubes = cubes.saturating_sub(1);
+        }
+
+        *chambers[to].influence.entry(player).or_default() += 1;
+
+        self.emit(GameEvent::InfluenceMoved { player, from, to });
+        self.refresh_chamber(from);
+        self.refresh_chamber(to);
+    }
+
+    // decree follows the majority, it stops working for the previous controller
+    fn refresh_chamber(&mut self, chamber: usize) {
+        let c = &mut self.agora.as_mut().unwrap().board.chambers[chamber];
+        let before = c.controller;
+        let after = c.get_controller();
+
+        if before == after {
+            return;
+        }
+
+        c.controller = after;
+
+        // face down decree is revealed to its first controller
+        if after.is_some() && c.decree.is_none() {
+            let agora = self.agora.as_mut().unwrap();
+            let decree = agora.decrees.remove(&chamber);
+            agora.board.chambers[chamber].decree = decree;
+
+            if let Some(decree) = decree {
+                self.emit(GameEvent::DecreeRevealed { chamber, decree });
+            }
+        }
+
+        if let Some(decree) = self.agora.as_ref().unwrap().board.chambers[chamber].decree {
+            let unit = get_decree(&decree);
+            let turn = self.players.me;
+
+            if let Some(p) = before {
+                self.players.set_turn(p);
+                unit.destruct(self);
+            }
+
+            if let Some(p) = after {
+                self.players.set_turn(p);
+                unit.construct(self);
+            }
+
+            self.players.set_turn(turn);
+        }
+        self.emit(GameEvent::ChamberControlChanged { chamber, player: after });
+
+        if let Some(p) = after {
+            let board = &self.agora.as_ref().unwrap().board;
+
+            if board.controlled_by(p).len() == board.chambers.len() {
+                state::over(self, Finisher::Winner(p), Victory::PoliticalSupremacy);
+            }
+        }
+    }
+
+    pub(crate) fn get_senate_actions(&self) -> Vec<Action> {
+        let Some(agora) = self.agora.as_ref() else {
+            return vec![];
+        };
+
+        let me = self.players.me;
+        let mut actions = vec![];
+
+        if self.get_influence_supply(me) > 0 {
+            actions.extend((0..agora.board.chambers.len()).map(Action::PlaceInfluence));
+        }
+
+        for from in 0..agora.board.chambers.len() {
+            if agora.board.chambers[from].influence_of(me) == 0 {
+                continue;
+            }
+
+            for to in [from.checked_sub(1), Some(from + 1)].into_iter().flatten() {
+                if to < agora.board.chambers.len() {
+                    actions.push(Action::MoveInfluence(from, to));
+                }
+            }
+        }
+
+        actions
+    }
+
+    pub(crate) fn get_removal_actions(&self) -> Vec<Action> {
+        let Some(agora) = self.agora.as_ref() else {
+            return vec![];
+        };
+
+        (0..agora.board.chambers.len())
+            .filter(|ind| agora.board.chambers[*ind].influence_of(self.players.enemy) > 0)
+            .map(Action::RemoveInfluence)
+            .collect()
+    }
+
+    // selection goes on while the unit has actions left
+    pub(crate) fn continue_senate(&mut self, effect: fn(Nickname, u8) -> PostEffect) {
+        if self.phase == Phase::Over {
+            return;
+        }
+
+        let Some(agora) = self.agora.as_mut() else {
+            return;
+        };
+
+        agora.actions = agora.actions.saturating_sub(1);
+
+        if agora.actions == 0 {
+            return;
+        }
+
+        let next = effect(self.players.me, agora.actions);
+        let possible = match next {
+            PostEffect::RemoveInfluence(..) => !self.get_removal_actions().is_empty(),
+            _ => true,
+        };
+
+        if possible {
+            self.post_effects.insert(0, next);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use rand::prelude::*;
+    use rand_chacha::ChaCha8Rng;
+    use super::*;
+    use crate::{notation, Options};
+
+    fn random_game(seed: u64) -> (Vec<Action>, HashSet<Phase>, State) {
+        let o = Options {
+            with_agora: true,
+            ..Default::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut actions = vec![Action::Prepare(crate::Setup::from_seed(1, 2, o, seed))];
+        let mut phases = HashSet::new();
+        let mut s = State::from(actions.clone()).expect("its ok");
+
+        while s.phase != Phase::Over {
+            phases.insert(s.phase);
+
+            let action = s.legal_actions().choose(&mut rng).cloned().expect("moves are offered until the end");
+            action.clone().apply(&mut s).expect("legal move is accepted");
+            actions.push(action);
+        }
+
+        (actions, phases, s)
+    }
+
+    #[test]
+    fn random_games() {
+        let mut phases = HashSet::new();
+
+        for seed in 0..50 {
+            let (actions, seen, s) = random_game(seed);
+
+            assert_eq!(Ok(actions.clone()), notation::parse(&notation::print(&actions)));
+            assert_eq!(s, State::from(actions).expect("its ok"));
+
+            let board = &s.agora.as_ref().unwrap().board;
+            assert!(s.finish.is_some());
+
+            for p in s.players.members() {
+                assert!(board.influence_of(p) <= INFLUENCE_CUBES);
+            }
+
+            phases.extend(seen);
+        }
+
+        for phase in [Phase::SenateSelection, Phase::InfluenceRemovalSelection, Phase::ConspiracySelection] {
+            assert!(phases.contains(&phase), "{:?} is never reached", phase);
+        }
+
+    }
+
+    #[test]
+    fn decree_follows_majority() {
+        let (actions, ..) = random_game(0);
+        let mut s = State::from(actions[..1].to_vec()).expect("its ok");
+        let (me, enemy) = (s.players.me, s.players.enemy);
+        s.agora.as_mut().unwrap().board.chambers[0].decree = Some(decree::Id::Treasury);
+
+        s.place_influence(0, me);
+        assert_eq!(Some(me), s.agora.as_ref().unwrap().board.chambers[0].controller);
+        assert_eq!(DEFAULT_DISCARD_REWARD + 1, s.me().bank.discard_reward);
+
+        s.place_influence(0, enemy);
+        assert_eq!(None, s.agora.as_ref().unwrap().board.chambers[0].controller);
+        assert_eq!(DEFAULT_DISCARD_REWARD, s.me().bank.discard_reward);
+
+        s.place_influence(0, enemy);
+        assert_eq!(Some(enemy), s.agora.as_ref().unwrap().board.chambers[0].controller);
+        assert_eq!(DEFAULT_DISCARD_REWARD + 1, s.enemy().bank.discard_reward);
+        assert_eq!(DEFAULT_DISCARD_REWARD, s.me().bank.discard_reward);
+        assert_eq!(INFLUENCE_CUBES - 2, s.get_influence_supply(enemy));
+    }
+
+    #[test]
+    fn face_down_decree_is_revealed_by_control() {
+        let (actions, ..) = random_game(0);
+        let Action::Prepare(setup) = actions[0].clone() else {
+            panic!("game starts with the setup");
+        };
+        let decrees = setup.agora.unwrap().decrees;
+        let mut s = State::from(actions[..1].to_vec()).expect("its ok");
+        let (me, enemy) = (s.players.me, s.players.enemy);
+        let decree = |s: &State, ind: usize| s.agora.as_ref().unwrap().board.chambers[ind].decree;
+
+        assert_eq!(Some(decrees[0]), decree(&s, 0));
+        assert_eq!(None, decree(&s, 1));
+        assert_eq!(None, s.view_for(me).agora.unwrap().chambers[1].decree);
+
+        s.place_influence(1, me);
+        s.place_influence(1, enemy);
+        assert_eq!(Some(decrees[1]), decree(&s, 1));
+        assert!(!s.agora.as_ref().unwrap().decrees.contains_key(&1));
+
+        // stays face up without a controller
+        s.place_influence(1, enemy);
+        s.remove_influence(1, enemy);
+        assert_eq!(None, s.agora.as_ref().unwrap().board.chambers[1].controller);
+        assert_eq!(Some(decrees[1]), s.view_for(enemy).agora.unwrap().chambers[1].decree);
+    }
+
+    #[test]
+    fn political_supremacy() {
+        let (actions, ..) = random_game(0);
+        let mut s = State::from(actions[..1].to_vec()).expect("its ok");
+        let me = s.players.me;
+
+        for chamber in 0..CHAMBERS_COUNT {
+            assert_eq!(None, s.finish);
+            s.place_influence(chamber, me);
+        }
+
+        assert_eq!(Some(me), s.finish.as_ref().map(|f| f.winner));
+        assert_eq!(Some(Victory::PoliticalSupremacy), s.finish.map(|f| f.victory));
+    }
+}
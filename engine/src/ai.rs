@@ -0,0 +1,471 @@
+//! Information set Monte Carlo tree search bot.
+//!
+//! Every iteration re-deals what the player on turn can not see: face down and upcoming
+//! buildings, the second wonders draft, random tokens, divinity piles and the conspiracies
+//! of the opponent. The tree is shared between the deals, its nodes are moves only.
+//! Age III positions without hidden units are solved exactly, see [`crate::solver`].
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use crate::{
+    prelude::*,
+    building,
+    game::Game,
+    pantheon,
+    solver::Solver,
+    token,
+    wonder,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Budget {
+    Iterations(u32),
+    Time(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub budget: Budget,
+    // UCB1 constant, rewards are 0 or 1
+    pub exploration: f64,
+    pub seed: u64,
+    // exact search once nothing is hidden, see crate::solver
+    pub endgame: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            budget: Budget::Iterations(1000),
+            exploration: 0.7,
+            seed: 0,
+            endgame: true,
+        }
+    }
+}
+
+/// Plays for whoever is on turn, see [`Game::turn`].
+#[derive(Debug, Clone)]
+pub struct Bot {
+    config: Config,
+    rng: ChaCha8Rng,
+    solver: Solver,
+}
+
+impl Bot {
+    pub fn new(config: Config) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(config.seed),
+            solver: Solver::new(),
+            config,
+        }
+    }
+
+    /// Best move in the current phase, none when the game is over or not started.
+    pub fn choose(&mut self, game: &Game) -> Option<Action> {
+        self.search(game.state())
+    }
+
+    pub(crate) fn search(&mut self, root: &State) -> Option<Action> {
+        let legal = root.legal_actions();
+
+        if legal.len() <= 1 {
+            return legal.into_iter().next();
+        }
+
+        if self.config.endgame {
+            if let Some(solution) = self.solver.search(root) {
+                return solution.line.into_iter().next();
+            }
+        }
+
+        let mut tree = vec![Node::new(None, root.players.me, None)];
+        let started = Instant::now();
+        let mut iterations = 0;
+
+        while !self.is_exhausted(iterations, started) {
+            let s = determinize(root, &mut self.rng);
+
+            self.iterate(&mut tree, s);
+            iterations += 1;
+        }
+
+        tree[0].children.iter()
+            .max_by_key(|child| tree[**child].visits)
+            .and_then(|child| tree[*child].action.clone())
+    }
+
+    fn is_exhausted(&self, iterations: u32, started: Instant) -> bool {
+        match self.config.budget {
+            Budget::Iterations(limit) => iterations >= limit,
+            // at least one iteration, so there is a move to return
+            Budget::Time(limit) => iterations > 0 && started.elapsed() >= limit,
+        }
+    }
+
+    fn iterate(&mut self, tree: &mut Vec<Node>, mut s: State) {
+        let mut node = 0;
+
+        // selection goes down while every move available in this deal is in the tree
+        while s.phase != Phase::Over {
+            let legal = s.legal_actions();
+            let untried = legal.iter()
+                .filter(|action| !tree[node].children.iter().any(|child| tree[*child].action.as_ref() == Some(*action)))
+                .collect::<Vec<_>>();
+
+            if let Some(action) = untried.choose(&mut self.rng) {
+                let child = tree.len();
+
+                tree.push(Node::new(Some((*action).clone()), s.players.me, Some(node)));
+                tree[node].children.push(child);
+                apply(&mut s, (*action).clone());
+                node = child;
+
+                break;
+            }
+
+            let available = tree[node].children.iter()
+                .copied()
+                .filter(|child| tree[*child].action.as_ref().is_some_and(|action| legal.contains(action)))
+                .collect::<Vec<_>>();
+
+            available.iter().for_each(|child| tree[*child].availability += 1);
+
+            let best = available.iter()
+                .copied()
+                .max_by(|a, b| tree[*a].ucb(self.config.exploration).total_cmp(&tree[*b].ucb(self.config.exploration)))
+                .expect("legal moves are offered until the end");
+
+            apply(&mut s, tree[best].action.clone().unwrap());
+            node = best;
+        }
+
+        while s.phase != Phase::Over {
+            let action = s.legal_actions().choose(&mut self.rng).cloned().expect("legal moves are offered until the end");
+            apply(&mut s, action);
+        }
+
+        let winner = s.finish.as_ref().map(|f| f.winner);
+        let mut current = Some(node);
+
+        while let Some(ind) = current {
+            let n = &mut tree[ind];
+
+            n.visits += 1;
+
+            if Some(n.player) == winner {
+                n.wins += 1;
+            }
+
+            current = n.parent;
+        }
+    }
+}
+
+impl Default for Bot {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    action: Option<Action>,
+    // who made the move leading to the node
+    player: Nickname,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    visits: u32,
+    wins: u32,
+    // deals where the move was legal while its parent was selected
+    availability: u32,
+}
+
+impl Node {
+    fn new(action: Option<Action>, player: Nickname, parent: Option<usize>) -> Self {
+        Self {
+            action,
+            player,
+            parent,
+            children: vec![],
+            visits: 0,
+            wins: 0,
+            availability: 1,
+        }
+    }
+
+    fn ucb(&self, exploration: f64) -> f64 {
+        let visits = self.visits.max(1) as f64;
+
+        self.wins as f64 / visits + exploration * ((self.availability as f64).ln() / visits).sqrt()
+    }
+}
+
+fn apply(s: &mut State, action: Action) {
+    action.apply(s).expect("legal move is accepted");
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+enum Class {
+    Common,
+    Agora,
+    Guild,
+    Temple,
+}
+
+impl Class {
+    // what a face down card reveals, counts per class follow the setup rules
+    fn of(id: &building::Id) -> Self {
+        match get_building(id).kind {
+            building::Kind::Guild => Self::Guild,
+            building::Kind::GrandTemple => Self::Temple,
+            building::Kind::Politician | building::Kind::Conspirator => Self::Agora,
+            _ => Self::Common,
+        }
+    }
+}
+
+// copy of the state where units hidden from the player on turn are dealt again
+pub(crate) fn determinize(s: &State, rng: &mut impl Rng) -> State {
+    let mut s = s.clone();
+
+    deal_buildings(&mut s, rng);
+    deal_wonders(&mut s, rng);
+    deal_tokens(&mut s, rng);
+    deal_divinities(&mut s, rng);
+    deal_conspiracies(&mut s, rng);
+    deal_decrees(&mut s, rng);
+
+    s
+}
+
+fn deal_buildings(s: &mut State, rng: &mut impl Rng) {
+    let deck_age = s.deck.buildings.first().map(|id| get_building(id).age);
+    let mut seen = s.cities.values()
+        .flat_map(|c| c.buildings.iter())
+        .chain(s.buildings.discarded.iter().copied())
+        .chain(s.interactive_units.buildings.iter().copied())
+        .chain(s.deck.buildings.iter().copied().filter(|id| !s.deck.is_face_down(id)))
+        .collect::<Vec<_>>();
+    seen.sort();
+
+    let mut ages = s.random_units.buildings.keys().copied().filter(|age| *age >= s.age).collect::<Vec<_>>();
+    ages.sort();
+
+    for age in ages {
+        let mut hidden = if deck_age == Some(age) {
+            s.deck.get_face_down_buildings()
+        } else {
+            s.random_units.buildings[&age].clone()
+        };
+        hidden.sort();
+
+        let mut pool = get_all_buildings().values()
+            .filter(|unit| unit.age == age && seen.binary_search(&unit.id).is_err())
+            .filter(|unit| match Class::of(&unit.id) {
+                Class::Temple => s.pantheon.is_some(),
+                Class::Agora => s.agora.is_some(),
+                _ => true,
+            })
+            .map(|unit| unit.id)
+            .collect::<Vec<_>>();
+        pool.sort();
+        pool.shuffle(rng);
+
+        let mut map = HashMap::new();
+
+        for id in hidden {
+            let class = Class::of(&id);
+
+            if let Some(pos) = pool.iter().position(|to| Class::of(to) == class) {
+                map.insert(id, pool.remove(pos));
+            }
+        }
+
+        if deck_age == Some(age) {
+            s.deck.substitute(&map);
+        }
+
+        if let Some(buildings) = s.random_units.buildings.get_mut(&age) {
+            buildings.iter_mut().for_each(|id| *id = map.get(id).copied().unwrap_or(*id));
+        }
+    }
+}
+
+// the second half of the draft is revealed after the first four picks
+fn deal_wonders(s: &mut State, rng: &mut impl Rng) {
+    let shown = WONDER_SELECTION_POOL_SIZE as usize;
+    let picked = s.cities.values().map(|c| c.wonders.len()).sum::<usize>();
+
+    if s.phase != Phase::WondersSelection || picked >= shown || s.random_units.wonders.len() <= shown {
+        return;
+    }
+
+    let visible = &s.random_units.wonders[..shown];
+    let with_promo = visible.iter().any(|id| wonder::Id::PROMO.contains(id));
+    let mut pool = get_all_wonders().keys()
+        .filter(|id| !visible.contains(id))
+        .filter(|id| {
+            if wonder::Id::PROMO.contains(id) {
+                with_promo
+            } else if wonder::Id::PANTHEON.contains(id) {
+                s.pantheon.is_some()
+            } else if wonder::Id::AGORA.contains(id) {
+                s.agora.is_some()
+            } else {
+                true
+            }
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    pool.sort();
+    pool.shuffle(rng);
+
+    for (ind, id) in pool.into_iter().enumerate().take(s.random_units.wonders.len() - shown) {
+        s.random_units.wonders[shown + ind] = id;
+    }
+}
+
+fn deal_tokens(s: &mut State, rng: &mut impl Rng) {
+    // offered ones are known to the chooser
+    if s.phase == Phase::RandomTokenSelection {
+        return;
+    }
+
+    let mut pool = get_all_tokens().keys()
+        .filter(|id| !s.tokens.contains(&Some(**id)))
+        .filter(|id| s.cities.values().all(|c| !c.tokens.contains(id)))
+        .copied()
+        .collect::<Vec<token::Id>>();
+    pool.sort();
+    pool.shuffle(rng);
+    pool.truncate(s.random_units.tokens.len());
+
+    if pool.len() == s.random_units.tokens.len() {
+        s.random_units.tokens = pool;
+    }
+}
+
+// pile contents are kept, only the order is unknown,
+// divinities the opponent placed face down come from the pile of their mythology
+fn deal_divinities(s: &mut State, rng: &mut impl Rng) {
+    let enemy = s.players.enemy;
+    let Some(pantheon) = s.pantheon.as_mut() else {
+        return;
+    };
+
+    for space in 0..pantheon::SPACES_COUNT {
+        let Some(id) = pantheon.board.spaces[space].filter(|_| pantheon.board.face_down[space] == Some(enemy)) else {
+            continue;
+        };
+
+        let pile = pantheon.piles.entry(get_divinity(&id).mythology).or_default();
+        pile.push(id);
+        pile.shuffle(rng);
+        pantheon.board.spaces[space] = pile.pop();
+    }
+
+    let mut mythologies = pantheon.piles.keys().copied().collect::<Vec<_>>();
+    mythologies.sort();
+
+    for m in mythologies {
+        pantheon.piles.get_mut(&m).unwrap().shuffle(rng);
+    }
+}
+
+// prepared conspiracies of the opponent come from the same pile
+fn deal_conspiracies(s: &mut State, rng: &mut impl Rng) {
+    let enemy = s.players.enemy;
+    let Some(agora) = s.agora.as_mut() else {
+        return;
+    };
+    let city = s.cities.get_mut(&enemy).unwrap();
+
+    let mut pool = agora.conspiracies.clone();
+    pool.extend(city.conspiracies.iter().flatten());
+    pool.sort();
+    pool.shuffle(rng);
+
+    for c in city.conspiracies.iter_mut().flatten() {
+        *c = pool.pop().unwrap();
+    }
+
+    agora.conspiracies = pool;
+}
+
+// face down decrees are unknown to both players
+fn deal_decrees(s: &mut State, rng: &mut impl Rng) {
+    let Some(agora) = s.agora.as_mut() else {
+        return;
+    };
+
+    let mut chambers = agora.decrees.keys().copied().collect::<Vec<_>>();
+    chambers.sort();
+
+    let mut pool = chambers.iter().map(|ind| agora.decrees[ind]).collect::<Vec<_>>();
+    pool.shuffle(rng);
+
+    agora.decrees = chambers.into_iter().zip(pool).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::game_11_actions,
+        Options,
+    };
+
+    #[test]
+    fn determinization_keeps_view() {
+        for len in [1, 5, 10, 30, 53] {
+            let s = State::from(game_11_actions()[..len].to_vec()).expect("its ok");
+            let view = s.view_for(s.players.me);
+            let mut rng = ChaCha8Rng::seed_from_u64(len as u64);
+            let mut redealt = false;
+
+            for _ in 0..10 {
+                let d = determinize(&s, &mut rng);
+
+                assert_eq!(view, d.view_for(d.players.me));
+                assert_eq!(s.legal_actions(), d.legal_actions());
+                redealt |= d.random_units.buildings != s.random_units.buildings;
+            }
+
+            assert!(redealt, "nothing is dealt again after {} actions", len);
+        }
+    }
+
+    #[test]
+    fn solves_endgame() {
+        let s = State::from(game_11_actions()[..72].to_vec()).expect("its ok");
+        let solution = Solver::new().search(&s).expect("nothing is hidden");
+
+        assert_eq!(Some(solution.line[0].clone()), Bot::default().search(&s));
+    }
+
+    #[test]
+    fn plays_until_the_end() {
+        let o = Options {
+            with_promo_wonders: true,
+            with_pantheon: true,
+            with_agora: true,
+        };
+        let mut game = Game::new(crate::Setup::from_seed(1, 2, o, 3)).expect("its ok");
+        let mut bot = Bot::new(Config {
+            budget: Budget::Iterations(4),
+            ..Default::default()
+        });
+
+        while game.phase() != Phase::Over {
+            let action = bot.choose(&game).expect("moves are offered until the end");
+
+            assert!(game.legal_actions().contains(&action));
+            game.apply(game.turn(), action).expect("legal move is accepted");
+        }
+
+        assert!(game.finish().is_some());
+        assert_eq!(None, bot.choose(&game));
+    }
+}
@@ -0,0 +1,404 @@
+//! Self-play between two agents over seeded setups, prints aggregate statistics.
+//!
+//! cargo run --release --bin simulate -- --games 1000 --agents mcts:500,greedy --expansions agora
+//!
+//! Every deal is played twice, the agents swap seats in the second game.
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use engine::{
+    ai::{Bot, Budget, Config},
+    token,
+    wonder,
+    Action,
+    Game,
+    Nickname,
+    Options,
+    Phase,
+    Score,
+    Setup,
+    Victory,
+};
+
+const USAGE: &str = "usage: simulate [--games N] [--agents A,B] [--seed N] [--seeds A,B] [--threads N] [--expansions promo,pantheon,agora]
+agents: random, greedy, mcts[:iterations]
+--seed deals the setups, --seeds drive the choices of each agent";
+
+const VICTORIES: [Victory; 6] = [
+    Victory::Civilian,
+    Victory::MilitarySupremacy,
+    Victory::ScienceSupremacy,
+    Victory::PoliticalSupremacy,
+    Victory::Resign,
+    Victory::Timeout,
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Agent {
+    Random,
+    // best score difference after the move
+    Greedy,
+    Mcts(u32),
+}
+
+impl Agent {
+    fn choose(&self, game: &Game, bot: &mut Bot, rng: &mut ChaCha8Rng) -> Action {
+        let legal = game.legal_actions();
+
+        match self {
+            Self::Random => legal.choose(rng).cloned(),
+            Self::Greedy => {
+                let me = game.turn();
+                let mut best = vec![];
+                let mut best_value = i32::MIN;
+
+                for action in legal {
+                    let mut next = game.clone();
+                    next.apply(me, action.clone()).expect("legal move is accepted");
+                    let value = evaluate(&next, me);
+
+                    if value > best_value {
+                        best_value = value;
+                        best.clear();
+                    }
+
+                    if value == best_value {
+                        best.push(action);
+                    }
+                }
+
+                best.choose(rng).cloned()
+            }
+            Self::Mcts(_) => bot.choose(game),
+        }
+            .expect("moves are offered until the end")
+    }
+}
+
+impl FromStr for Agent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "random" => Ok(Self::Random),
+            None if s == "greedy" => Ok(Self::Greedy),
+            None if s == "mcts" => Ok(Self::Mcts(1000)),
+            // the search needs at least one iteration to expand the root
+            Some(("mcts", iterations)) => iterations.parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .map(Self::Mcts)
+                .ok_or_else(|| format!("wrong iterations: {}", iterations)),
+            _ => Err(format!("unknown agent: {}", s)),
+        }
+    }
+}
+
+fn evaluate(game: &Game, me: Nickname) -> i32 {
+    if let Some(finish) = game.finish() {
+        return if finish.winner == me { i32::MAX } else { i32::MIN + 1 };
+    }
+
+    let [p1, p2] = game.players();
+    let enemy = if p1 == me { p2 } else { p1 };
+    let total = |p| game.city(p).map_or(0, |c| c.score.total as i32);
+
+    total(me) - total(enemy)
+}
+
+#[derive(Debug)]
+struct Args {
+    games: u32,
+    agents: [Agent; 2],
+    seed: u64,
+    agent_seeds: [u64; 2],
+    threads: usize,
+    options: Options,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self {
+            games: 100,
+            agents: [Agent::Greedy, Agent::Random],
+            seed: 0,
+            agent_seeds: [0, 1],
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            options: Options::default(),
+        };
+
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or(format!("missing value of {}", flag))?;
+            let wrong = |_| format!("wrong value of {}: {}", flag, value);
+
+            match flag.as_str() {
+                "--games" => {
+                    parsed.games = value.parse().map_err(wrong)?;
+
+                    if parsed.games == 0 {
+                        return Err("at least one game expected".to_string());
+                    }
+                }
+                "--seed" => parsed.seed = value.parse().map_err(wrong)?,
+                "--seeds" => {
+                    let seeds = value.split(',').map(str::parse).collect::<Result<Vec<u64>, _>>().map_err(wrong)?;
+                    parsed.agent_seeds = seeds.try_into().map_err(|_| format!("two seeds expected: {}", value))?;
+                }
+                "--threads" => parsed.threads = value.parse::<usize>().map_err(wrong)?.max(1),
+                "--agents" => {
+                    let agents = value.split(',').map(str::parse).collect::<Result<Vec<Agent>, _>>()?;
+                    parsed.agents = agents.try_into().map_err(|_| format!("two agents expected: {}", value))?;
+                }
+                "--expansions" => {
+                    for expansion in value.split(',') {
+                        match expansion {
+                            "promo" => parsed.options.with_promo_wonders = true,
+                            "pantheon" => parsed.options.with_pantheon = true,
+                            "agora" => parsed.options.with_agora = true,
+                            _ => return Err(format!("unknown expansion: {}", expansion)),
+                        }
+                    }
+                }
+                _ => return Err(format!("unknown flag: {}", flag)),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+// one finished game, everything is indexed by agent
+#[derive(Debug)]
+struct Record {
+    winner: usize,
+    victory: Victory,
+    scores: [Score; 2],
+    wonders: [Vec<(wonder::Id, bool)>; 2],
+    tokens: [Vec<token::Id>; 2],
+    moves: u32,
+}
+
+// pairs of games share the deal, the agents swap seats in the odd one
+fn play(args: &Args, number: u32) -> Record {
+    let seed = args.seed + (number / 2) as u64;
+    let seats: [Nickname; 2] = if number.is_multiple_of(2) { [1, 2] } else { [2, 1] };
+    let mut game = Game::new(Setup::from_seed(1, 2, args.options, seed)).expect("valid setup");
+
+    // own stream of each agent for every game
+    let mut rngs = args.agent_seeds.map(|agent_seed| {
+        let mut rng = ChaCha8Rng::seed_from_u64(agent_seed);
+        rng.set_stream(number as u64);
+        rng
+    });
+    let mut bots = [0, 1].map(|agent| Bot::new(Config {
+        budget: Budget::Iterations(match args.agents[agent] {
+            Agent::Mcts(iterations) => iterations,
+            _ => 0,
+        }),
+        seed: rngs[agent].gen(),
+        ..Default::default()
+    }));
+    let mut moves = 0;
+
+    while game.phase() != Phase::Over {
+        let player = game.turn();
+        let agent = seats.iter().position(|p| *p == player).unwrap();
+        let action = args.agents[agent].choose(&game, &mut bots[agent], &mut rngs[agent]);
+
+        game.apply(player, action).expect("legal move is accepted");
+        moves += 1;
+    }
+
+    let finish = game.finish().expect("game is over");
+    let city = |agent: usize| game.city(seats[agent]).expect("player is seated");
+
+    Record {
+        winner: seats.iter().position(|p| *p == finish.winner).unwrap(),
+        victory: finish.victory,
+        scores: [0, 1].map(|agent| city(agent).score),
+        wonders: [0, 1].map(|agent| city(agent).wonders.iter().map(|(id, b)| (*id, b.is_some())).collect()),
+        tokens: [0, 1].map(|agent| city(agent).tokens.iter().collect()),
+        moves,
+    }
+}
+
+fn simulate(args: &Args) -> Vec<Record> {
+    let next = AtomicU32::new(0);
+    let records = Mutex::new(vec![]);
+
+    thread::scope(|scope| {
+        for _ in 0..args.threads {
+            scope.spawn(|| loop {
+                let number = next.fetch_add(1, Ordering::Relaxed);
+
+                if number >= args.games {
+                    break;
+                }
+
+                let record = play(args, number);
+                records.lock().unwrap().push((number, record));
+            });
+        }
+    });
+
+    // the order of finished games does not depend on threads
+    let mut records = records.into_inner().unwrap();
+    records.sort_by_key(|(number, _)| *number);
+
+    records.into_iter().map(|(_, record)| record).collect()
+}
+
+type Category = (&'static str, fn(&Score) -> u8);
+
+#[derive(Debug, Default)]
+struct UnitStats {
+    picked: u32,
+    built: u32,
+    won: u32,
+}
+
+fn percent(part: u32, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+fn report(args: &Args, records: &[Record]) {
+    let games = records.len() as u32;
+    let names = args.agents.map(|agent| format!("{:?}", agent));
+
+    println!("{} games, seed {}, agent seeds {:?}, {:?}", games, args.seed, args.agent_seeds, args.options);
+    println!();
+    println!("{:<20} {:>12} {:>12}", "wins", names[0], names[1]);
+
+    let wins = |agent| records.iter().filter(|r| r.winner == agent).count() as u32;
+    println!("{:<20} {:>11.1}% {:>11.1}%", "total", percent(wins(0), games), percent(wins(1), games));
+
+    for victory in VICTORIES {
+        let wins = |agent| records.iter().filter(|r| r.winner == agent && r.victory == victory).count() as u32;
+
+        if wins(0) + wins(1) > 0 {
+            println!("{:<20} {:>11.1}% {:>11.1}%", format!("{:?}", victory), percent(wins(0), games), percent(wins(1), games));
+        }
+    }
+
+    println!();
+    println!("{:<20} {:>12} {:>12}", "average score", names[0], names[1]);
+
+    let categories: [Category; 12] = [
+        ("civilian", |s| s.civilian),
+        ("science", |s| s.science),
+        ("commercial", |s| s.commercial),
+        ("guilds", |s| s.guilds),
+        ("temples", |s| s.temples),
+        ("divinities", |s| s.divinities),
+        ("politics", |s| s.politics),
+        ("wonders", |s| s.wonders),
+        ("tokens", |s| s.tokens),
+        ("coins", |s| s.coins),
+        ("military", |s| s.military),
+        ("total", |s| s.total),
+    ];
+
+    for (name, points) in categories {
+        let average = |agent: usize| records.iter().map(|r| points(&r.scores[agent]) as f64).sum::<f64>() / games as f64;
+        println!("{:<20} {:>12.2} {:>12.2}", name, average(0), average(1));
+    }
+
+    let mut wonders = BTreeMap::<wonder::Id, UnitStats>::new();
+    let mut tokens = BTreeMap::<token::Id, UnitStats>::new();
+
+    for r in records {
+        for agent in [0, 1] {
+            let won = (r.winner == agent) as u32;
+
+            for (id, built) in &r.wonders[agent] {
+                let stats = wonders.entry(*id).or_default();
+                stats.picked += 1;
+                stats.built += *built as u32;
+                stats.won += won;
+            }
+
+            for id in &r.tokens[agent] {
+                let stats = tokens.entry(*id).or_default();
+                stats.picked += 1;
+                stats.built += 1;
+                stats.won += won;
+            }
+        }
+    }
+
+    // pick rate is per game, win rate is of the player who picked the unit
+    println!();
+    println!("{:<28} {:>8} {:>8} {:>8}", "wonder", "picked", "built", "won");
+
+    for (id, stats) in &wonders {
+        println!(
+            "{:<28} {:>7.1}% {:>7.1}% {:>7.1}%",
+            format!("{:?}", id),
+            percent(stats.picked, games),
+            percent(stats.built, stats.picked),
+            percent(stats.won, stats.picked),
+        );
+    }
+
+    println!();
+    println!("{:<28} {:>8} {:>8}", "token", "picked", "won");
+
+    for (id, stats) in &tokens {
+        println!("{:<28} {:>7.1}% {:>7.1}%", format!("{:?}", id), percent(stats.picked, games), percent(stats.won, stats.picked));
+    }
+
+    let moves = records.iter().map(|r| r.moves).collect::<Vec<_>>();
+
+    println!();
+    println!(
+        "moves per game: average {:.1}, min {}, max {}",
+        moves.iter().sum::<u32>() as f64 / games as f64,
+        moves.iter().min().unwrap_or(&0),
+        moves.iter().max().unwrap_or(&0),
+    );
+}
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let records = simulate(&args);
+
+    report(&args, &records);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Args, String> {
+        Args::parse(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn args() {
+        let args = parse("--games 10 --agents mcts:50,random --seeds 7,9").expect("its ok");
+
+        assert_eq!(10, args.games);
+        assert_eq!([Agent::Mcts(50), Agent::Random], args.agents);
+        assert_eq!([7, 9], args.agent_seeds);
+
+        assert!(parse("--games 0").is_err());
+        assert!(parse("--seeds 7").is_err());
+        assert!(parse("--agents greedy").is_err());
+        assert!(parse("--agents mcts:0,random").is_err());
+    }
+}
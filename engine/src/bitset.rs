@@ -0,0 +1,208 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+// ids a set can hold
+#[cfg(feature = "definitions")]
+pub const CAPACITY: usize = u128::BITS as usize;
+
+/// Unit id with a dense position among all ids of its kind.
+pub trait Member: Copy + Ord + 'static {
+    // every id in ascending order, at most CAPACITY of them
+    fn all() -> &'static [Self];
+
+    fn index(&self) -> usize {
+        Self::all().binary_search(self).expect("id is registered")
+    }
+}
+
+/// Set of unit ids packed into a single word, iterated in ascending order.
+pub struct BitSet<T> {
+    bits: u128,
+    member: PhantomData<T>,
+}
+
+impl<T: Member> BitSet<T> {
+    pub fn new() -> Self {
+        Self {
+            bits: 0,
+            member: PhantomData,
+        }
+    }
+
+    // returns whether the id was absent
+    pub fn insert(&mut self, id: T) -> bool {
+        let bit = 1 << id.index();
+        let absent = self.bits & bit == 0;
+        self.bits |= bit;
+
+        absent
+    }
+
+    // returns whether the id was present
+    pub fn remove(&mut self, id: &T) -> bool {
+        let bit = 1 << id.index();
+        let present = self.bits & bit != 0;
+        self.bits &= !bit;
+
+        present
+    }
+
+    pub fn contains(&self, id: &T) -> bool {
+        self.bits & (1 << id.index()) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn iter(&self) -> Iter<T> {
+        Iter {
+            bits: self.bits,
+            member: PhantomData,
+        }
+    }
+}
+
+pub struct Iter<T> {
+    bits: u128,
+    member: PhantomData<T>,
+}
+
+impl<T: Member> Iterator for Iter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.bits == 0 {
+            return None;
+        }
+
+        let index = self.bits.trailing_zeros() as usize;
+        self.bits &= self.bits - 1;
+
+        Some(T::all()[index])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bits.count_ones() as usize;
+
+        (len, Some(len))
+    }
+}
+
+impl<T: Member> ExactSizeIterator for Iter<T> {}
+
+impl<T: Member> IntoIterator for &BitSet<T> {
+    type Item = T;
+    type IntoIter = Iter<T>;
+
+    fn into_iter(self) -> Iter<T> {
+        self.iter()
+    }
+}
+
+impl<T: Member> FromIterator<T> for BitSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+
+        set
+    }
+}
+
+impl<T: Member> Extend<T> for BitSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for id in iter {
+            self.insert(id);
+        }
+    }
+}
+
+impl<T: Member, const N: usize> From<[T; N]> for BitSet<T> {
+    fn from(ids: [T; N]) -> Self {
+        ids.into_iter().collect()
+    }
+}
+
+// derives would ask the ids for the same traits
+impl<T: Member> Default for BitSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for BitSet<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for BitSet<T> {}
+
+impl<T> PartialEq for BitSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl<T> Eq for BitSet<T> {}
+
+impl<T> Hash for BitSet<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits.hash(state);
+    }
+}
+
+impl<T: Member + fmt::Debug> fmt::Debug for BitSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+// same format as a list of ids
+#[cfg(feature = "serde")]
+impl<T: Member + serde::Serialize> serde::Serialize for BitSet<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Member + serde::Deserialize<'de>> serde::Deserialize<'de> for BitSet<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{building, token};
+
+    #[test]
+    fn every_id_fits() {
+        assert!(<building::Id as Member>::all().len() <= 128);
+        assert!(<token::Id as Member>::all().len() <= 128);
+
+        let all = <building::Id as Member>::all().iter().copied().collect::<BitSet<_>>();
+
+        assert_eq!(<building::Id as Member>::all(), all.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut set = BitSet::from([building::Id::Stable, building::Id::LumberYard]);
+
+        assert!(!set.insert(building::Id::Stable));
+        assert!(set.insert(building::Id::Orator));
+        assert!(set.remove(&building::Id::Stable));
+        assert!(!set.remove(&building::Id::Stable));
+        assert!(!set.contains(&building::Id::Stable));
+        assert_eq!(vec![building::Id::LumberYard, building::Id::Orator], set.iter().collect::<Vec<_>>());
+        assert_eq!(2, set.len());
+    }
+}
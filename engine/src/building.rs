@@ -1,13 +1,18 @@
 use std::collections::HashMap;
 use std::iter::{IntoIterator, Iterator};
 use std::sync::LazyLock;
+#[cfg(feature = "definitions")]
+use std::sync::OnceLock;
 use std::vec;
 use crate::{
     prelude::*,
+    bitset::Member,
     economy::PayScope,
 };
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u16)]
 pub enum Id {
     LumberYard = 100,
     LoggingCamp,
@@ -85,9 +90,36 @@ pub enum Id {
     ScientistsGuild,
     MoneyLendersGuild,
     TacticiansGuild,
+
+    EgyptianTemple = 500,
+    GreekTemple,
+    MesopotamianTemple,
+    PhoenicianTemple,
+    RomanTemple,
+
+    Orator = 600,
+    Tribune,
+    Demagogue,
+    Agitator,
+    Informer,
+    Consul,
+    Censor,
+    Aedile,
+    Plotter,
+    Spy,
+    Dictator,
+    Quaestor,
+    Proconsul,
+    Usurper,
+    Assassin,
+
+    // new building of installed definitions, sorts after the built-in ones
+    #[cfg(feature = "definitions")]
+    Custom(u8),
 }
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Kind {
     RawMaterials = 1,
     ManufacturedGoods,
@@ -96,8 +128,20 @@ pub enum Kind {
     Civilian,
     Commercial,
     Guild,
+    GrandTemple,
+    Politician,
+    Conspirator,
 }
 
+impl Kind {
+    pub fn is_expansion(&self) -> bool {
+        matches!(self, Self::GrandTemple | Self::Politician | Self::Conspirator)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct Unit {
     pub id: Id,
     pub age: Age,
@@ -112,18 +156,29 @@ impl BaseUnit for Unit {
     }
 }
 
-pub fn filter_by_kind(source: &[Id], kind: Kind) -> Vec<Id> {
+pub fn filter_by_kind(source: impl IntoIterator<Item = Id>, kind: Kind) -> Vec<Id> {
     source
-        .iter()
+        .into_iter()
         .filter(|id| get_building(id).kind == kind)
-        .copied()
         .collect::<Vec<_>>()
 }
 
-pub fn count_by_kind(source: &[Id], kind: Kind) -> u8 {
+pub fn count_by_kind(source: impl IntoIterator<Item = Id>, kind: Kind) -> u8 {
     filter_by_kind(source, kind).len() as u8
 }
 
+impl Member for Id {
+    #[cfg(feature = "definitions")]
+    fn all() -> &'static [Self] {
+        INSTALLED.get().map_or(&ORDERED, |(_, ids)| ids)
+    }
+
+    #[cfg(not(feature = "definitions"))]
+    fn all() -> &'static [Self] {
+        &ORDERED
+    }
+}
+
 static REGISTRY: LazyLock<HashMap<Id, Unit>> = LazyLock::new(|| {
     vec![
         Unit {
@@ -1203,16 +1258,352 @@ static REGISTRY: LazyLock<HashMap<Id, Unit>> = LazyLock::new(|| {
                 Effect::Guild(Bonus::Military, 1, 1),
             ],
         },
+        Unit {
+            id: Id::EgyptianTemple,
+            age: Age::III,
+            kind: Kind::GrandTemple,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 2),
+                    (Resource::Papyrus, 1),
+                    (Resource::Glass, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::GrandTemple,
+            ],
+        },
+        Unit {
+            id: Id::GreekTemple,
+            age: Age::III,
+            kind: Kind::GrandTemple,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 1),
+                    (Resource::Wood, 1),
+                    (Resource::Glass, 1),
+                    (Resource::Papyrus, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::GrandTemple,
+            ],
+        },
+        Unit {
+            id: Id::MesopotamianTemple,
+            age: Age::III,
+            kind: Kind::GrandTemple,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Clay, 2),
+                    (Resource::Papyrus, 1),
+                    (Resource::Glass, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::GrandTemple,
+            ],
+        },
+        Unit {
+            id: Id::PhoenicianTemple,
+            age: Age::III,
+            kind: Kind::GrandTemple,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Wood, 2),
+                    (Resource::Clay, 1),
+                    (Resource::Glass, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::GrandTemple,
+            ],
+        },
+        Unit {
+            id: Id::RomanTemple,
+            age: Age::III,
+            kind: Kind::GrandTemple,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 1),
+                    (Resource::Clay, 1),
+                    (Resource::Wood, 1),
+                    (Resource::Papyrus, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::GrandTemple,
+            ],
+        },
+        // provisional agora cards, see crate::agora
+        Unit {
+            id: Id::Orator,
+            age: Age::I,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 1,
+                resources: Resources::new(),
+            },
+            effects: vec![
+                Effect::Senate(1),
+            ],
+        },
+        Unit {
+            id: Id::Tribune,
+            age: Age::I,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Wood, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Senate(1),
+                Effect::Points(1),
+            ],
+        },
+        Unit {
+            id: Id::Demagogue,
+            age: Age::I,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Senate(1),
+                Effect::Coins(2),
+            ],
+        },
+        Unit {
+            id: Id::Agitator,
+            age: Age::I,
+            kind: Kind::Conspirator,
+            cost: Cost {
+                coins: 2,
+                resources: Resources::new(),
+            },
+            effects: vec![
+                Effect::Conspire,
+            ],
+        },
+        Unit {
+            id: Id::Informer,
+            age: Age::I,
+            kind: Kind::Conspirator,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Papyrus, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Conspire,
+            ],
+        },
+        Unit {
+            id: Id::Consul,
+            age: Age::II,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Wood, 1),
+                    (Resource::Glass, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Senate(2),
+            ],
+        },
+        Unit {
+            id: Id::Censor,
+            age: Age::II,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 1),
+                    (Resource::Papyrus, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Senate(2),
+                Effect::Points(1),
+            ],
+        },
+        Unit {
+            id: Id::Aedile,
+            age: Age::II,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Clay, 2),
+                ]),
+            },
+            effects: vec![
+                Effect::Senate(2),
+            ],
+        },
+        Unit {
+            id: Id::Plotter,
+            age: Age::II,
+            kind: Kind::Conspirator,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 1),
+                    (Resource::Glass, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Conspire,
+                Effect::Points(1),
+            ],
+        },
+        Unit {
+            id: Id::Spy,
+            age: Age::II,
+            kind: Kind::Conspirator,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Clay, 1),
+                    (Resource::Papyrus, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Conspire,
+            ],
+        },
+        Unit {
+            id: Id::Dictator,
+            age: Age::III,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 2),
+                    (Resource::Glass, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Senate(3),
+                Effect::Points(2),
+            ],
+        },
+        Unit {
+            id: Id::Quaestor,
+            age: Age::III,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Wood, 2),
+                    (Resource::Papyrus, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Senate(3),
+                Effect::Points(1),
+            ],
+        },
+        Unit {
+            id: Id::Proconsul,
+            age: Age::III,
+            kind: Kind::Politician,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Clay, 2),
+                    (Resource::Papyrus, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Senate(3),
+                Effect::Points(2),
+            ],
+        },
+        Unit {
+            id: Id::Usurper,
+            age: Age::III,
+            kind: Kind::Conspirator,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Wood, 1),
+                    (Resource::Clay, 1),
+                    (Resource::Glass, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Conspire,
+                Effect::Points(2),
+            ],
+        },
+        Unit {
+            id: Id::Assassin,
+            age: Age::III,
+            kind: Kind::Conspirator,
+            cost: Cost {
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 1),
+                    (Resource::Papyrus, 1),
+                    (Resource::Glass, 1),
+                ]),
+            },
+            effects: vec![
+                Effect::Conspire,
+                Effect::Points(1),
+            ],
+        },
     ]
         .into_iter()
         .map(|unit| (unit.id, unit))
         .collect::<HashMap<_, _>>()
 });
 
+static ORDERED: LazyLock<Vec<Id>> = LazyLock::new(|| get_ordered(&REGISTRY));
+
+// definitions installed over the built-in set with their ids in order, see crate::definition
+#[cfg(feature = "definitions")]
+static INSTALLED: OnceLock<(HashMap<Id, Unit>, Vec<Id>)> = OnceLock::new();
+
+fn get_ordered(units: &HashMap<Id, Unit>) -> Vec<Id> {
+    let mut ids = units.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    ids
+}
+
 pub fn get(id: &Id) -> &Unit {
-    REGISTRY.get(id).unwrap()
+    get_all().get(id).unwrap()
+}
+
+#[cfg(feature = "definitions")]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    INSTALLED.get().map_or(&REGISTRY, |(units, _)| units)
 }
 
+#[cfg(not(feature = "definitions"))]
 pub fn get_all() -> &'static HashMap<Id, Unit> {
     &REGISTRY
-}
\ No newline at end of file
+}
+
+// once per process, false when the set is already replaced
+#[cfg(feature = "definitions")]
+pub(crate) fn install(units: HashMap<Id, Unit>) -> bool {
+    let ids = get_ordered(&units);
+
+    INSTALLED.set((units, ids)).is_ok()
+}
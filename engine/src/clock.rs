@@ -0,0 +1,84 @@
+use crate::prelude::*;
+
+// time is given by the caller in milliseconds, from any fixed origin
+pub type Millis = u64;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeControl {
+    // initial time, added back after every move
+    Fischer {
+        base: Millis,
+        increment: Millis,
+    },
+    // unused time is not carried over
+    PerMove(Millis),
+}
+
+impl TimeControl {
+    fn initial(&self) -> Millis {
+        match *self {
+            Self::Fischer { base, .. } => base,
+            Self::PerMove(limit) => limit,
+        }
+    }
+}
+
+/// Remaining time of both players, only the one of the player on turn runs.
+///
+/// Every action is a move, selections after a construction are timed as well.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clock {
+    control: TimeControl,
+    remaining: [(Nickname, Millis); 2],
+    // when the current move started
+    since: Millis,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, players: [Nickname; 2], now: Millis) -> Self {
+        Self {
+            control,
+            remaining: players.map(|p| (p, control.initial())),
+            since: now,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Time left before the current move started.
+    pub fn remaining(&self, player: Nickname) -> Millis {
+        self.remaining.iter()
+            .find(|(p, _)| *p == player)
+            .map_or(0, |(_, time)| *time)
+    }
+
+    /// Time spent on the current move so far.
+    pub fn elapsed(&self, now: Millis) -> Millis {
+        now.saturating_sub(self.since)
+    }
+
+    pub fn is_flagged(&self, player: Nickname, now: Millis) -> bool {
+        self.elapsed(now) >= self.remaining(player)
+    }
+
+    // ends the move of the player, returns the time spent on it
+    pub(crate) fn punch(&mut self, player: Nickname, now: Millis) -> Millis {
+        let spent = self.elapsed(now);
+        let control = self.control;
+
+        if let Some((_, time)) = self.remaining.iter_mut().find(|(p, _)| *p == player) {
+            *time = match control {
+                TimeControl::Fischer { increment, .. } => time.saturating_sub(spent) + increment,
+                TimeControl::PerMove(limit) => limit,
+            };
+        }
+
+        self.since = now;
+
+        spent
+    }
+}
@@ -0,0 +1,119 @@
+//! Provisional conspiracies of the Agora expansion, not the printed cards, see [`crate::agora`].
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+#[cfg(feature = "definitions")]
+use std::sync::OnceLock;
+use crate::{
+    prelude::*,
+    building,
+};
+
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Id {
+    Arson = 1,
+    Blackmail,
+    Coup,
+    Espionage,
+    Extortion,
+    Sabotage,
+    Treason,
+    Uprising,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Unit {
+    pub id: Id,
+    pub effects: Vec<Effect>,
+}
+
+impl BaseUnit for Unit {
+    fn effects(&self) -> &Vec<Effect> {
+        &self.effects
+    }
+}
+
+static REGISTRY: LazyLock<HashMap<Id, Unit>> = LazyLock::new(|| {
+    vec![
+        Unit {
+            id: Id::Arson,
+            effects: vec![
+                Effect::DiscardLayoutBuilding,
+            ],
+        },
+        Unit {
+            id: Id::Blackmail,
+            effects: vec![
+                Effect::Fine(4),
+                Effect::Points(1),
+            ],
+        },
+        Unit {
+            id: Id::Coup,
+            effects: vec![
+                Effect::RemoveInfluence(2),
+            ],
+        },
+        Unit {
+            id: Id::Espionage,
+            effects: vec![
+                Effect::PickRandomToken,
+            ],
+        },
+        Unit {
+            id: Id::Extortion,
+            effects: vec![
+                Effect::StealBuilding(vec![building::Kind::RawMaterials, building::Kind::ManufacturedGoods]),
+            ],
+        },
+        Unit {
+            id: Id::Sabotage,
+            effects: vec![
+                Effect::DestructBuilding(building::Kind::Commercial),
+            ],
+        },
+        Unit {
+            id: Id::Treason,
+            effects: vec![
+                Effect::RemoveInfluence(1),
+                Effect::Senate(1),
+            ],
+        },
+        Unit {
+            id: Id::Uprising,
+            effects: vec![
+                Effect::Military(2, false),
+            ],
+        },
+    ]
+        .into_iter()
+        .map(|unit| (unit.id, unit))
+        .collect::<HashMap<_,_>>()
+});
+
+// definitions installed over the built-in set, see crate::definition
+#[cfg(feature = "definitions")]
+static INSTALLED: OnceLock<HashMap<Id, Unit>> = OnceLock::new();
+
+pub fn get(id: &Id) -> &Unit {
+    get_all().get(id).unwrap()
+}
+
+#[cfg(feature = "definitions")]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    INSTALLED.get().unwrap_or(&REGISTRY)
+}
+
+#[cfg(not(feature = "definitions"))]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    &REGISTRY
+}
+
+// once per process, false when the set is already replaced
+#[cfg(feature = "definitions")]
+pub(crate) fn install(units: HashMap<Id, Unit>) -> bool {
+    INSTALLED.set(units).is_ok()
+}
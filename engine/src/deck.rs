@@ -38,51 +38,111 @@ pub fn get_layout(age: Age) -> &'static str {
     LAYOUTS[age as usize - 1].trim()
 }
 
-#[derive(Default, Debug)]
+// positions in the age buildings list which are dealt face down
+pub fn get_face_down_slots(age: Age) -> Vec<usize> {
+    let mut slots = vec![];
+    let mut pos = 0usize;
+
+    for (ind, line) in get_layout(age).lines().skip(1).enumerate() {
+        let count = line.matches('[').count();
+
+        if ind % 2 == 1 {
+            slots.extend(pos..pos + count);
+        }
+
+        pos += count;
+    }
+
+    slots
+}
+
+// pyramids hold 20 buildings at most, slots are bits of the masks below
+const MAX_SLOTS: usize = 20;
+
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Deck {
+    // by slot, in the layout order
     pub buildings: Vec<building::Id>,
-    pub scheme: Vec<Line>,
-    pub graph: HashMap<building::Id, Children>,
-    pub face_down: HashSet<building::Id>,
+    // slots of the next line lying on each slot
+    covers: [u32; MAX_SLOTS],
+    // dealt face down, revealed once nothing lies on them
+    face_down: u32,
+    taken: u32,
+    top_line: usize,
 }
 
 impl Deck {
     pub fn new(layout: &str, buildings: Vec<building::Id>) -> Self {
-        let scheme = Self::build_scheme(layout, &buildings);
-        let graph = Self::build_graph(&scheme);
-
-        let face_down = scheme.iter()
+        // start position of each building in the line, a building takes two chars
+        let lines = layout.lines()
             .skip(1)
-            .step_by(2)
-            .flatten()
-            .cloned()
-            .flatten()
-            .collect();
+            .map(|line| line.match_indices('[').map(|(pos, _)| pos).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut covers = [0; MAX_SLOTS];
+        let mut face_down = 0;
+        let mut slot = 0;
+
+        for (ind, line) in lines.iter().enumerate() {
+            let next_slot = slot + line.len();
+
+            for pos in line {
+                if ind % 2 == 1 {
+                    face_down |= 1 << slot;
+                }
+
+                // children lie on the first char of the building and on the one after it
+                if let Some(next) = lines.get(ind + 1) {
+                    for (offset, next_pos) in next.iter().enumerate() {
+                        if *next_pos + 1 >= *pos && *next_pos <= pos + 2 {
+                            covers[slot] |= 1 << (next_slot + offset);
+                        }
+                    }
+                }
+
+                slot += 1;
+            }
+        }
 
         Self {
+            top_line: lines.first().map(|line| line.len()).unwrap_or_default(),
             buildings,
-            scheme,
-            graph,
+            covers,
             face_down,
+            taken: 0,
         }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.graph.is_empty()
+        self.taken.count_ones() as usize == self.buildings.len()
+    }
+
+    pub fn is_face_down(&self, id: &building::Id) -> bool {
+        self.find(id).is_some_and(|slot| self.is_hidden(slot))
+    }
+
+    pub fn get_face_down_buildings(&self) -> Vec<building::Id> {
+        (0..self.buildings.len())
+            .filter(|slot| self.is_hidden(*slot))
+            .map(|slot| self.buildings[slot])
+            .collect()
     }
 
     pub fn get_public_layout(&self) -> Layout {
-        self.buildings.iter()
-            .map(|id| {
-                if !self.graph.contains_key(id) {
+        (0..self.buildings.len())
+            .map(|slot| {
+                let id = &self.buildings[slot];
+
+                if self.is_taken(slot) {
                     return Slot::Empty;
                 }
 
-                if self.face_down.contains(id) {
-                    return if get_building(id).kind == building::Kind::Guild {
-                        Slot::FaceDownGuild
-                    } else {
-                        Slot::FaceDown
+                if self.is_hidden(slot) {
+                    return match get_building(id).kind {
+                        building::Kind::Guild => Slot::FaceDownGuild,
+                        building::Kind::GrandTemple => Slot::FaceDownTemple,
+                        _ => Slot::FaceDown,
                     }
                 }
 
@@ -92,36 +152,40 @@ impl Deck {
     }
 
     pub fn get_playable_buildings(&self) -> HashSet<building::Id> {
-        self.graph.iter()
-            .filter_map(|(parent, children)| {
-                if children.iter().flatten().count() == 0 {
-                    Some(*parent)
-                } else {
-                    None
-                }
-            })
+        (0..self.buildings.len())
+            .filter(|slot| !self.is_taken(*slot) && !self.is_covered(*slot))
+            .map(|slot| self.buildings[slot])
             .collect()
     }
 
     pub fn get_returned_buildings(&self) -> Vec<building::Id> {
         let age = get_building(&self.buildings[0]).age;
 
-        get_all_buildings().values()
+        let mut buildings = get_all_buildings().values()
             .filter_map(|item| {
-                if item.age == age && !self.buildings.contains(&item.id) {
+                // expansion cards are not a part of the base box
+                if item.age == age && !item.kind.is_expansion() && !self.buildings.contains(&item.id) {
                     Some(item.id)
                 } else {
                     None
                 }
             })
+            .collect::<Vec<_>>();
+        buildings.sort();
+
+        buildings
+    }
+
+    pub fn get_remaining_buildings(&self) -> Vec<building::Id> {
+        (0..self.buildings.len())
+            .filter(|slot| !self.is_taken(*slot))
+            .map(|slot| self.buildings[slot])
             .collect()
     }
 
     pub fn get_top_line_buildings(&self) -> Vec<building::Id> {
-        let top_line_buildings_count = self.scheme[0].iter().flatten().count() / 2;
-
         self.get_public_layout().iter()
-            .take(top_line_buildings_count)
+            .take(self.top_line)
             .filter_map(|slot| {
                 if let Slot::FaceUp(id) = slot {
                     Some(*id)
@@ -133,95 +197,55 @@ impl Deck {
     }
 
     pub fn pull_building(&mut self, id: &building::Id) {
-        self.graph.remove(id);
-        self.graph.iter_mut()
-            .for_each(|(parent, children)| {
-                if let Some(left) = children[0] {
-                    if &left == id {
-                        *children.get_mut(0).unwrap() = None;
-                    }
-                }
-
-                if let Some(right) = children[1] {
-                    if &right == id {
-                        *children.get_mut(1).unwrap() = None;
-                    }
-                }
-
-                if children.iter().flatten().count() == 0 {
-                    self.face_down.remove(parent);
-                }
-            })
+        if let Some(slot) = self.find(id) {
+            self.taken |= 1 << slot;
+        }
     }
 
-    fn build_scheme(layout: &str, buildings: &[building::Id]) -> Vec<Line> {
-        let mut scheme: Vec<Line> = Vec::with_capacity(layout.lines().count());
-        let mut building_pos = 0usize;
-
-        for layout_line in layout.lines().skip(1) {
-            let mut line = Line::default();
-
-            for (ind, char) in layout_line.chars().enumerate() {
-                if char == '[' {
-                    line[ind] = Some(buildings[building_pos]);
-                    line[ind + 1] = Some(buildings[building_pos]);
-                    building_pos += 1;
-                }
+    // swaps building ids in place, the layout shape and what is taken stay the same
+    pub(crate) fn substitute(&mut self, map: &HashMap<building::Id, building::Id>) {
+        for id in self.buildings.iter_mut() {
+            if let Some(to) = map.get(id) {
+                *id = *to;
             }
-
-            scheme.push(line);
         }
-
-        scheme
     }
 
-    fn build_graph(scheme: &[Line]) -> HashMap<building::Id, Children> {
-        let mut graph: HashMap<building::Id, Children> = Default::default();
-        let mut it = scheme.iter().peekable();
-
-        while let Some(line) = it.next() {
-            for (pos, slot) in line.iter().enumerate()  {
-                if let Some(id) = slot {
-                    let mut nodes: Children = Default::default();
+    // slots already taken, the rest of the deck follows from the layout and the buildings
+    pub(crate) fn taken(&self) -> u32 {
+        self.taken
+    }
 
-                    if let Some(next) = it.peek() {
-                        if pos > 0 {
-                            if let Some(Some(left)) = next.get(pos-1) {
-                                nodes[0] = Some(*left)
-                            }
-                        }
+    fn find(&self, id: &building::Id) -> Option<usize> {
+        self.buildings.iter().position(|item| item == id)
+    }
 
-                        if let Some(Some(right)) = next.get(pos+1) {
-                            nodes[1] = Some(*right)
-                        }
-                    }
+    fn is_taken(&self, slot: usize) -> bool {
+        self.taken & (1 << slot) != 0
+    }
 
-                    graph.insert(*id, nodes);
-                }
-            }
-        }
+    fn is_covered(&self, slot: usize) -> bool {
+        self.covers[slot] & !self.taken != 0
+    }
 
-        graph
+    fn is_hidden(&self, slot: usize) -> bool {
+        self.face_down & (1 << slot) != 0 && !self.is_taken(slot) && self.is_covered(slot)
     }
 }
 
 pub type Layout = Vec<Slot>;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Slot {
     #[default]
     Empty,
     FaceDown,
     FaceDownGuild,
+    FaceDownTemple,
     FaceUp(building::Id),
 }
 
-// track in which positions placed item
-// each building keep 2 slots
-// suggest 10 buildings is enough(6 max currently)
-type Line = [Option<building::Id>; 20];
-type Children = [Option<building::Id>; 2];
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,44 +276,39 @@ mod tests {
             Theater,//119
         ]);
 
-        assert_eq!(
-            d.face_down,
-            HashSet::from([
-                ClayPool,
-                ClayPit,
-                Quarry,
-                Workshop,
-                Apothecary,
-                StoneReserve,
-                ClayReserve,
-                WoodReserve,
-            ]),
-        );
+        let mut face_down = d.get_face_down_buildings();
+        face_down.sort();
 
+        assert_eq!(vec![ClayPool, ClayPit, Quarry, Workshop, Apothecary, StoneReserve, ClayReserve, WoodReserve], face_down);
         assert_eq!(
-            d.graph,
-            HashMap::from([
-                (LumberYard, [Some(ClayPool), Some(ClayPit)]),
-                (LoggingCamp, [Some(ClayPit), Some(Quarry)]),
-                (ClayPool, [Some(StonePit), Some(GlassWorks)]),
-                (ClayPit, [Some(GlassWorks), Some(Press)]),
-                (Quarry, [Some(Press), Some(GuardTower)]),
-                (StonePit, [Some(Workshop), Some(Apothecary)]),
-                (GlassWorks, [Some(Apothecary), Some(StoneReserve)]),
-                (Press, [Some(StoneReserve), Some(ClayReserve)]),
-                (GuardTower, [Some(ClayReserve), Some(WoodReserve)]),
-                (Workshop, [Some(Stable), Some(Garrison)]),
-                (Apothecary, [Some(Garrison), Some(Palisade)]),
-                (StoneReserve, [Some(Palisade), Some(Scriptorium)]),
-                (ClayReserve, [Some(Scriptorium), Some(Pharmacist)]),
-                (WoodReserve, [Some(Pharmacist), Some(Theater)]),
-                (Stable, [None, None]),
-                (Garrison, [None, None]),
-                (Palisade, [None, None]),
-                (Scriptorium, [None, None]),
-                (Pharmacist, [None, None]),
-                (Theater, [None, None]),
-            ]),
+            HashSet::from([Stable, Garrison, Palisade, Scriptorium, Pharmacist, Theater]),
+            d.get_playable_buildings(),
         );
+
+        let mut d = d;
+        d.pull_building(&Stable);
+        assert!(d.is_face_down(&Workshop));
+
+        d.pull_building(&Garrison);
+        assert!(!d.is_face_down(&Workshop));
+        assert!(d.get_playable_buildings().contains(&Workshop));
+        assert!(!d.get_playable_buildings().contains(&Apothecary));
+        assert_eq!(Slot::Empty, d.get_public_layout()[14]);
+        assert_eq!(Slot::FaceUp(Workshop), d.get_public_layout()[9]);
+        assert_eq!(Slot::FaceDown, d.get_public_layout()[10]);
+
+        for id in d.buildings.clone() {
+            d.pull_building(&id);
+        }
+
+        assert!(d.is_empty());
+        assert!(Deck::default().is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn face_down_slots() {
+        assert_eq!(vec![2, 3, 4, 9, 10, 11, 12, 13], get_face_down_slots(Age::I));
+        assert_eq!(vec![6, 7, 8, 9, 10, 15, 16, 17], get_face_down_slots(Age::II));
+        assert_eq!(vec![2, 3, 4, 9, 10, 15, 16, 17], get_face_down_slots(Age::III));
+    }
+}
@@ -0,0 +1,124 @@
+//! Provisional decrees of the Agora expansion, not the printed tokens, see [`crate::agora`].
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+#[cfg(feature = "definitions")]
+use std::sync::OnceLock;
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Id {
+    Builders = 1,
+    Engineers,
+    Artisans,
+    Architects,
+    Treasury,
+    Glory,
+    Priesthood,
+}
+
+// decree works while its chamber is controlled, so effects must be reversible
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Unit {
+    pub id: Id,
+    pub effects: Vec<Effect>,
+}
+
+impl BaseUnit for Unit {
+    fn effects(&self) -> &Vec<Effect> {
+        &self.effects
+    }
+}
+
+static REGISTRY: LazyLock<HashMap<Id, Unit>> = LazyLock::new(|| {
+    vec![
+        Unit {
+            id: Id::Builders,
+            effects: vec![
+                Effect::Discounter {
+                    scope: PayScope::Wonders,
+                    resources: Resource::ALL.to_vec(),
+                    count: 1,
+                },
+            ],
+        },
+        Unit {
+            id: Id::Engineers,
+            effects: vec![
+                Effect::Discounter {
+                    scope: PayScope::Global,
+                    resources: Resource::RAW_MATERIALS.to_vec(),
+                    count: 1,
+                },
+            ],
+        },
+        Unit {
+            id: Id::Artisans,
+            effects: vec![
+                Effect::Discounter {
+                    scope: PayScope::Global,
+                    resources: Resource::MANUFACTURED_GOODS.to_vec(),
+                    count: 1,
+                },
+            ],
+        },
+        Unit {
+            id: Id::Architects,
+            effects: vec![
+                Effect::Discounter {
+                    scope: PayScope::Civilian,
+                    resources: Resource::ALL.to_vec(),
+                    count: 1,
+                },
+            ],
+        },
+        Unit {
+            id: Id::Treasury,
+            effects: vec![
+                Effect::DiscardRewardAdjuster,
+            ],
+        },
+        Unit {
+            id: Id::Glory,
+            effects: vec![
+                Effect::Points(3),
+            ],
+        },
+        Unit {
+            id: Id::Priesthood,
+            effects: vec![
+                Effect::DivinityDiscount(1),
+            ],
+        },
+    ]
+        .into_iter()
+        .map(|unit| (unit.id, unit))
+        .collect::<HashMap<_,_>>()
+});
+
+// definitions installed over the built-in set, see crate::definition
+#[cfg(feature = "definitions")]
+static INSTALLED: OnceLock<HashMap<Id, Unit>> = OnceLock::new();
+
+pub fn get(id: &Id) -> &Unit {
+    get_all().get(id).unwrap()
+}
+
+#[cfg(feature = "definitions")]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    INSTALLED.get().unwrap_or(&REGISTRY)
+}
+
+#[cfg(not(feature = "definitions"))]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    &REGISTRY
+}
+
+// once per process, false when the set is already replaced
+#[cfg(feature = "definitions")]
+pub(crate) fn install(units: HashMap<Id, Unit>) -> bool {
+    INSTALLED.set(units).is_ok()
+}
@@ -0,0 +1,473 @@
+//! Unit definitions loaded from JSON over the built-in registries.
+//!
+//! A file redefines units of the engine or adds new buildings, wonders and tokens under `Custom` ids.
+//! Ages and kinds may change as long as every deck can still be dealt.
+//! Start from [`Definitions::current`] printed with [`Definitions::to_json`].
+//! Definitions are installed once per process, before the first game is prepared.
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use crate::{
+    prelude::*,
+    agora,
+    bitset,
+    building::{self, Kind},
+    conspiracy,
+    decree,
+    divinity,
+    error::DefinitionError,
+    token,
+    wonder,
+};
+
+// set by the first prepared game, units must not change under it
+static PREPARED: Mutex<bool> = Mutex::new(false);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum UnitId {
+    Building(building::Id),
+    Wonder(wonder::Id),
+    Token(token::Id),
+    Divinity(divinity::Id),
+    Decree(decree::Id),
+    Conspiracy(conspiracy::Id),
+}
+
+/// Units to redefine, the ones left out keep their built-in definition.
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Definitions {
+    pub buildings: Vec<building::Unit>,
+    pub wonders: Vec<wonder::Unit>,
+    pub tokens: Vec<token::Unit>,
+    pub divinities: Vec<divinity::Unit>,
+    pub decrees: Vec<decree::Unit>,
+    pub conspiracies: Vec<conspiracy::Unit>,
+}
+
+impl Definitions {
+    // every unit of the active set, ordered by id
+    pub fn current() -> Self {
+        let mut buildings = get_all_buildings().values().cloned().collect::<Vec<_>>();
+        let mut wonders = get_all_wonders().values().cloned().collect::<Vec<_>>();
+        let mut tokens = get_all_tokens().values().cloned().collect::<Vec<_>>();
+        let mut divinities = get_all_divinities().values().cloned().collect::<Vec<_>>();
+        let mut decrees = get_all_decrees().values().cloned().collect::<Vec<_>>();
+        let mut conspiracies = get_all_conspiracies().values().cloned().collect::<Vec<_>>();
+
+        buildings.sort_by_key(|unit| unit.id);
+        wonders.sort_by_key(|unit| unit.id);
+        tokens.sort_by_key(|unit| unit.id);
+        divinities.sort_by_key(|unit| unit.id);
+        decrees.sort_by_key(|unit| unit.id);
+        conspiracies.sort_by_key(|unit| unit.id);
+
+        Self {
+            buildings,
+            wonders,
+            tokens,
+            divinities,
+            decrees,
+            conspiracies,
+        }
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, DefinitionError> {
+        let definitions = serde_json::from_str::<Self>(source)
+            .map_err(|e| DefinitionError::Parse(e.to_string()))?;
+
+        let costs = serde_json::from_str::<SourceCosts>(source)
+            .map_err(|e| DefinitionError::Parse(e.to_string()))?;
+
+        for unit in costs.buildings {
+            validate_cost(UnitId::Building(unit.id), &unit.cost.resources)?;
+        }
+
+        for unit in costs.wonders {
+            validate_cost(UnitId::Wonder(unit.id), &unit.cost.resources)?;
+        }
+
+        definitions.validate()?;
+
+        Ok(definitions)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn validate(&self) -> Result<(), DefinitionError> {
+        let mut seen = HashSet::new();
+
+        for unit in &self.buildings {
+            let id = UnitId::Building(unit.id);
+
+            if !seen.insert(id) {
+                return Err(DefinitionError::DuplicateUnit(id));
+            }
+
+            validate_effects(id, &unit.effects)?;
+        }
+
+        for unit in &self.wonders {
+            let id = UnitId::Wonder(unit.id);
+
+            if !seen.insert(id) {
+                return Err(DefinitionError::DuplicateUnit(id));
+            }
+
+            validate_effects(id, &unit.effects)?;
+        }
+
+        for unit in &self.tokens {
+            let id = UnitId::Token(unit.id);
+
+            if !seen.insert(id) {
+                return Err(DefinitionError::DuplicateUnit(id));
+            }
+
+            validate_effects(id, &unit.effects)?;
+        }
+
+        for unit in &self.divinities {
+            let id = UnitId::Divinity(unit.id);
+            let builtin = get_divinity(&unit.id);
+
+            if !seen.insert(id) {
+                return Err(DefinitionError::DuplicateUnit(id));
+            }
+
+            if unit.mythology != builtin.mythology {
+                return Err(DefinitionError::WrongMythology(unit.id, builtin.mythology));
+            }
+
+            validate_effects(id, &unit.effects)?;
+        }
+
+        for unit in &self.decrees {
+            let id = UnitId::Decree(unit.id);
+
+            if !seen.insert(id) {
+                return Err(DefinitionError::DuplicateUnit(id));
+            }
+
+            validate_effects(id, &unit.effects)?;
+        }
+
+        for unit in &self.conspiracies {
+            let id = UnitId::Conspiracy(unit.id);
+
+            if !seen.insert(id) {
+                return Err(DefinitionError::DuplicateUnit(id));
+            }
+
+            validate_effects(id, &unit.effects)?;
+        }
+
+        let mut buildings = get_all_buildings().clone();
+        buildings.extend(self.buildings.iter().map(|unit| (unit.id, unit.clone())));
+        validate_buildings(&buildings)?;
+
+        let tokens = get_all_tokens().keys()
+            .chain(self.tokens.iter().map(|unit| &unit.id))
+            .collect::<HashSet<_>>();
+
+        if tokens.len() > bitset::CAPACITY {
+            return Err(DefinitionError::TooManyUnits(tokens.len()));
+        }
+
+        Ok(())
+    }
+
+    // replaces the registries for the rest of the process, refused once a game is prepared
+    pub fn install(self) -> Result<(), DefinitionError> {
+        self.validate()?;
+
+        // games wait for the whole set to be replaced
+        let prepared = PREPARED.lock().unwrap();
+
+        if *prepared {
+            return Err(DefinitionError::GamePrepared);
+        }
+
+        let mut buildings = get_all_buildings().clone();
+        let mut wonders = get_all_wonders().clone();
+        let mut tokens = get_all_tokens().clone();
+        let mut divinities = get_all_divinities().clone();
+        let mut decrees = get_all_decrees().clone();
+        let mut conspiracies = get_all_conspiracies().clone();
+
+        buildings.extend(self.buildings.into_iter().map(|unit| (unit.id, unit)));
+        wonders.extend(self.wonders.into_iter().map(|unit| (unit.id, unit)));
+        tokens.extend(self.tokens.into_iter().map(|unit| (unit.id, unit)));
+        divinities.extend(self.divinities.into_iter().map(|unit| (unit.id, unit)));
+        decrees.extend(self.decrees.into_iter().map(|unit| (unit.id, unit)));
+        conspiracies.extend(self.conspiracies.into_iter().map(|unit| (unit.id, unit)));
+
+        // buildings go first, the lock keeps the other registries in step
+        if !building::install(buildings) {
+            return Err(DefinitionError::AlreadyInstalled);
+        }
+
+        wonder::install(wonders);
+        token::install(tokens);
+        divinity::install(divinities);
+        decree::install(decrees);
+        conspiracy::install(conspiracies);
+
+        Ok(())
+    }
+}
+
+// zero counts are left out of the compact cost, so they are looked for in the source
+#[derive(serde::Deserialize)]
+struct SourceCosts {
+    #[serde(default)]
+    buildings: Vec<SourceUnit<building::Id>>,
+    #[serde(default)]
+    wonders: Vec<SourceUnit<wonder::Id>>,
+}
+
+#[derive(serde::Deserialize)]
+struct SourceUnit<T> {
+    id: T,
+    cost: SourceCost,
+}
+
+#[derive(serde::Deserialize)]
+struct SourceCost {
+    resources: HashMap<Resource, u8>,
+}
+
+fn validate_cost(id: UnitId, resources: &HashMap<Resource, u8>) -> Result<(), DefinitionError> {
+    if resources.values().any(|count| *count == 0) {
+        return Err(DefinitionError::InvalidCost(id));
+    }
+
+    Ok(())
+}
+
+// buildings after the definitions are installed, every deck must be dealt with any options
+fn validate_buildings(buildings: &HashMap<building::Id, building::Unit>) -> Result<(), DefinitionError> {
+    if buildings.len() > bitset::CAPACITY {
+        return Err(DefinitionError::TooManyUnits(buildings.len()));
+    }
+
+    let mut units = buildings.values().collect::<Vec<_>>();
+    units.sort_by_key(|unit| unit.id);
+
+    for unit in units.iter() {
+        for effect in &unit.effects {
+            if let Effect::Chain(chain) = effect {
+                match buildings.get(chain) {
+                    None => return Err(DefinitionError::UnknownUnit(UnitId::Building(*chain))),
+                    Some(next) if next.age <= unit.age => return Err(DefinitionError::WrongChain(unit.id, *chain)),
+                    _ => (),
+                }
+            }
+        }
+
+        // guilds and grand temples complete the deck of the last age
+        if matches!(unit.kind, Kind::Guild | Kind::GrandTemple) && !unit.age.is_last() {
+            return Err(DefinitionError::WrongAge(unit.id, Age::III));
+        }
+    }
+
+    let count = |filter: &dyn Fn(&building::Unit) -> bool| units.iter().filter(|unit| filter(unit)).count();
+
+    for age in Age::ALL {
+        let deck = count(&|unit| unit.age == age && unit.kind != Kind::Guild && !unit.kind.is_expansion());
+        let needed = if age.is_last() { DECK_LIMIT - GUILDS_LIMIT } else { DECK_LIMIT };
+
+        if deck < needed as usize {
+            return Err(DefinitionError::BuildingsCount(age, deck));
+        }
+
+        let agora = count(&|unit| unit.age == age && matches!(unit.kind, Kind::Politician | Kind::Conspirator));
+
+        if agora < agora::AGORA_BUILDINGS_LIMIT as usize {
+            return Err(DefinitionError::AgoraBuildingsCount(age, agora));
+        }
+    }
+
+    for kind in [Kind::Guild, Kind::GrandTemple] {
+        let last = count(&|unit| unit.kind == kind);
+
+        if last < GUILDS_LIMIT as usize {
+            return Err(DefinitionError::LastAgeCount(kind, last));
+        }
+    }
+
+    Ok(())
+}
+
+// units stay as installed from the first prepared game on
+pub(crate) fn lock() {
+    *PREPARED.lock().unwrap() = true;
+}
+
+fn validate_effects(id: UnitId, effects: &[Effect]) -> Result<(), DefinitionError> {
+    for effect in effects {
+        let empty = match effect {
+            Effect::Discounter { resources, count, .. } => resources.is_empty() || *count == 0,
+            Effect::FixedResourcePrice(resources) => resources.is_empty(),
+            Effect::StealBuilding(kinds) => kinds.is_empty(),
+            Effect::Military(power, _) => *power == 0,
+            Effect::Resource(_, count) | Effect::Senate(count) | Effect::RemoveInfluence(count) => *count == 0,
+            _ => false,
+        };
+
+        if empty {
+            return Err(DefinitionError::InvalidEffect(id, effect.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_round_trip() {
+        let definitions = Definitions::current();
+
+        assert_eq!(Ok(()), definitions.validate());
+        assert_eq!(Ok(definitions.clone()), Definitions::from_json(&definitions.to_json()));
+    }
+
+    #[test]
+    fn partial_file() {
+        let source = r#"{
+            "buildings": [
+                {
+                    "id": "LumberYard",
+                    "age": "I",
+                    "kind": "RawMaterials",
+                    "cost": {"coins": 1, "resources": {}},
+                    "effects": [{"Resource": ["Wood", 2]}]
+                }
+            ]
+        }"#;
+
+        let definitions = Definitions::from_json(source).expect("its ok");
+
+        assert_eq!(1, definitions.buildings.len());
+        assert!(definitions.wonders.is_empty());
+        assert_eq!(vec![Effect::Resource(Resource::Wood, 2)], definitions.buildings[0].effects);
+    }
+
+    #[test]
+    fn invalid_units() {
+        let builtin = |id| get_building(&id).clone();
+
+        let mut unit = builtin(building::Id::MerchantsGuild);
+        unit.age = Age::II;
+        assert_eq!(
+            Err(DefinitionError::WrongAge(building::Id::MerchantsGuild, Age::III)),
+            Definitions { buildings: vec![unit], ..Default::default() }.validate(),
+        );
+
+        // age I keeps 20 of its 23 buildings
+        let moved = [building::Id::Stable, building::Id::Garrison, building::Id::Palisade, building::Id::Tavern].map(|id| {
+            let mut unit = builtin(id);
+            unit.age = Age::II;
+            unit.effects.retain(|effect| !matches!(effect, Effect::Chain(_)));
+            unit
+        });
+        assert_eq!(Ok(()), Definitions { buildings: moved[..3].to_vec(), ..Default::default() }.validate());
+        assert_eq!(
+            Err(DefinitionError::BuildingsCount(Age::I, 19)),
+            Definitions { buildings: moved.to_vec(), ..Default::default() }.validate(),
+        );
+
+        let mut unit = builtin(building::Id::Stable);
+        unit.id = building::Id::Custom(1);
+        unit.effects.push(Effect::Chain(building::Id::Custom(2)));
+        assert_eq!(
+            Err(DefinitionError::UnknownUnit(UnitId::Building(building::Id::Custom(2)))),
+            Definitions { buildings: vec![unit], ..Default::default() }.validate(),
+        );
+
+        let mut unit = builtin(building::Id::Stable);
+        unit.effects.push(Effect::Chain(building::Id::Garrison));
+        assert_eq!(
+            Err(DefinitionError::WrongChain(building::Id::Stable, building::Id::Garrison)),
+            Definitions { buildings: vec![unit], ..Default::default() }.validate(),
+        );
+
+        let source = r#"{"buildings": [{
+            "id": "Stable",
+            "age": "I",
+            "kind": "Military",
+            "cost": {"coins": 0, "resources": {"Wood": 0}},
+            "effects": [{"Military": [1, true]}]
+        }]}"#;
+        assert_eq!(
+            Err(DefinitionError::InvalidCost(UnitId::Building(building::Id::Stable))),
+            Definitions::from_json(source),
+        );
+
+        let unit = builtin(building::Id::Stable);
+        assert_eq!(
+            Err(DefinitionError::DuplicateUnit(UnitId::Building(building::Id::Stable))),
+            Definitions { buildings: vec![unit.clone(), unit], ..Default::default() }.validate(),
+        );
+
+        let token = token::Unit {
+            id: token::Id::Urbanism,
+            effects: vec![Effect::Resource(Resource::Wood, 0)],
+        };
+        assert!(matches!(
+            Definitions { tokens: vec![token], ..Default::default() }.validate(),
+            Err(DefinitionError::InvalidEffect(UnitId::Token(token::Id::Urbanism), _)),
+        ));
+
+        let mut unit = get_divinity(&divinity::Id::Zeus).clone();
+        unit.mythology = divinity::Mythology::Roman;
+        assert_eq!(
+            Err(DefinitionError::WrongMythology(divinity::Id::Zeus, divinity::Mythology::Greek)),
+            Definitions { divinities: vec![unit], ..Default::default() }.validate(),
+        );
+
+        for source in [
+            r#"{"buildings": [{"id": "Unknown"}]}"#,
+            r#"{"cards": []}"#,
+            r#"{"tokens": [{"id": "Urbanism", "effects": [], "points": 2}]}"#,
+        ] {
+            assert!(matches!(Definitions::from_json(source), Err(DefinitionError::Parse(_))), "{}", source);
+        }
+    }
+
+    #[test]
+    fn new_units() {
+        let source = r#"{
+            "buildings": [{
+                "id": {"Custom": 1},
+                "age": "II",
+                "kind": "Civilian",
+                "cost": {"coins": 1, "resources": {"Stone": 1}},
+                "effects": [{"Points": 3}]
+            }],
+            "wonders": [{
+                "id": {"Custom": 1},
+                "cost": {"coins": 0, "resources": {"Wood": 2}},
+                "effects": [{"Coins": 4}]
+            }],
+            "tokens": [{"id": {"Custom": 1}, "effects": [{"Points": 4}]}]
+        }"#;
+
+        let definitions = Definitions::from_json(source).expect("its ok");
+
+        assert_eq!(building::Id::Custom(1), definitions.buildings[0].id);
+        assert_eq!(wonder::Id::Custom(1), definitions.wonders[0].id);
+        assert_eq!(token::Id::Custom(1), definitions.tokens[0].id);
+    }
+
+    #[test]
+    fn refused_after_prepared_game() {
+        crate::Game::new(crate::Setup::from_seed(1, 2, Default::default(), 1)).expect("its ok");
+
+        assert_eq!(Err(DefinitionError::GamePrepared), Definitions::current().install());
+    }
+}
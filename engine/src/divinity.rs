@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+#[cfg(feature = "definitions")]
+use std::sync::OnceLock;
+use crate::{
+    prelude::*,
+    building,
+};
+
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mythology {
+    Egyptian = 1,
+    Greek,
+    Mesopotamian,
+    Phoenician,
+    Roman,
+}
+
+impl Mythology {
+    pub const ALL: [Self; 5] = [
+        Self::Egyptian,
+        Self::Greek,
+        Self::Mesopotamian,
+        Self::Phoenician,
+        Self::Roman,
+    ];
+
+    // mythology token makes the temple free, like a chain
+    pub fn temple(&self) -> building::Id {
+        match self {
+            Self::Egyptian => building::Id::EgyptianTemple,
+            Self::Greek => building::Id::GreekTemple,
+            Self::Mesopotamian => building::Id::MesopotamianTemple,
+            Self::Phoenician => building::Id::PhoenicianTemple,
+            Self::Roman => building::Id::RomanTemple,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Id {
+    Anubis = 1,
+    Isis,
+    Ra,
+    Aphrodite,
+    Hades,
+    Zeus,
+    Enki,
+    Ishtar,
+    Nisaba,
+    Astarte,
+    Baal,
+    Tanit,
+    Mars,
+    Minerva,
+    Neptune,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Unit {
+    pub id: Id,
+    pub mythology: Mythology,
+    pub effects: Vec<Effect>,
+}
+
+impl BaseUnit for Unit {
+    fn effects(&self) -> &Vec<Effect> {
+        &self.effects
+    }
+}
+
+static REGISTRY: LazyLock<HashMap<Id, Unit>> = LazyLock::new(|| {
+    vec![
+        Unit {
+            id: Id::Anubis,
+            mythology: Mythology::Egyptian,
+            effects: vec![
+                Effect::DestructWonder,
+            ],
+        },
+        Unit {
+            id: Id::Isis,
+            mythology: Mythology::Egyptian,
+            effects: vec![
+                Effect::ConstructDiscardedWonder,
+            ],
+        },
+        Unit {
+            id: Id::Ra,
+            mythology: Mythology::Egyptian,
+            effects: vec![
+                Effect::StealWonder,
+            ],
+        },
+        Unit {
+            id: Id::Aphrodite,
+            mythology: Mythology::Greek,
+            effects: vec![
+                Effect::Points(9),
+            ],
+        },
+        Unit {
+            id: Id::Hades,
+            mythology: Mythology::Greek,
+            effects: vec![
+                Effect::PickDiscardedBuilding,
+            ],
+        },
+        Unit {
+            id: Id::Zeus,
+            mythology: Mythology::Greek,
+            effects: vec![
+                Effect::DiscardLayoutBuilding,
+            ],
+        },
+        Unit {
+            id: Id::Enki,
+            mythology: Mythology::Mesopotamian,
+            effects: vec![
+                Effect::PickRandomToken,
+            ],
+        },
+        Unit {
+            id: Id::Ishtar,
+            mythology: Mythology::Mesopotamian,
+            effects: vec![
+                Effect::Science(ScientificSymbol::Law),
+            ],
+        },
+        Unit {
+            id: Id::Nisaba,
+            mythology: Mythology::Mesopotamian,
+            effects: vec![
+                Effect::CopyScientificSymbol,
+            ],
+        },
+        Unit {
+            id: Id::Astarte,
+            mythology: Mythology::Phoenician,
+            effects: vec![
+                Effect::Coins(7),
+            ],
+        },
+        Unit {
+            id: Id::Baal,
+            mythology: Mythology::Phoenician,
+            effects: vec![
+                Effect::StealBuilding(vec![building::Kind::RawMaterials, building::Kind::ManufacturedGoods]),
+            ],
+        },
+        Unit {
+            id: Id::Tanit,
+            mythology: Mythology::Phoenician,
+            effects: vec![
+                Effect::Coins(12),
+            ],
+        },
+        Unit {
+            id: Id::Mars,
+            mythology: Mythology::Roman,
+            effects: vec![
+                Effect::Military(2, false),
+            ],
+        },
+        Unit {
+            id: Id::Minerva,
+            mythology: Mythology::Roman,
+            effects: vec![
+                Effect::Minerva,
+            ],
+        },
+        Unit {
+            id: Id::Neptune,
+            mythology: Mythology::Roman,
+            effects: vec![
+                Effect::Loot,
+            ],
+        },
+    ]
+        .into_iter()
+        .map(|unit| (unit.id, unit))
+        .collect::<HashMap<_,_>>()
+});
+
+// definitions installed over the built-in set, see crate::definition
+#[cfg(feature = "definitions")]
+static INSTALLED: OnceLock<HashMap<Id, Unit>> = OnceLock::new();
+
+pub fn get(id: &Id) -> &Unit {
+    get_all().get(id).unwrap()
+}
+
+#[cfg(feature = "definitions")]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    INSTALLED.get().unwrap_or(&REGISTRY)
+}
+
+#[cfg(not(feature = "definitions"))]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    &REGISTRY
+}
+
+// once per process, false when the set is already replaced
+#[cfg(feature = "definitions")]
+pub(crate) fn install(units: HashMap<Id, Unit>) -> bool {
+    INSTALLED.set(units).is_ok()
+}
+
+pub fn filter_by_mythology(m: Mythology) -> Vec<Id> {
+    let mut divinities = get_all().values()
+        .filter(|d| d.mythology == m)
+        .map(|d| d.id)
+        .collect::<Vec<_>>();
+    divinities.sort();
+
+    divinities
+}
@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 use std::iter::Iterator;
-use crate::building;
+use std::ops::{Index, IndexMut};
+use crate::{building, decree, token, wonder};
 use crate::prelude::get_building;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PayScope {
     Global,
     Civilian,
@@ -20,7 +22,8 @@ impl PayScope {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Discount {
     pub scope: PayScope,
     pub resources: Vec<Resource>,
@@ -28,30 +31,78 @@ pub struct Discount {
 }
 
 impl Discount {
-    pub fn apply(&self, cost: &mut Cost, priority: &[Resource]) {
-        let mut reserve = self.count;
+    // every way to spend the whole reserve on the resources left
+    fn get_shares(&self, left: &Resources) -> Vec<Resources> {
+        let reserve = self.count.min(self.resources.iter().map(|r| left[r]).sum());
+        let mut shares = vec![];
 
-        priority.iter()
-            .for_each(|resource| {
-                if !self.resources.contains(resource) || reserve == 0 {
-                    return;
-                }
+        split(&self.resources, reserve, left, Resources::new(), &mut shares);
+
+        shares
+    }
+}
 
-                if let Some(count) = cost.resources.get_mut(resource) {
-                    let n = if *count < reserve {
-                        *count
-                    } else {
-                        reserve
-                    };
+fn split(resources: &[Resource], reserve: u8, left: &Resources, share: Resources, shares: &mut Vec<Resources>) {
+    let Some((resource, rest)) = resources.split_first() else {
+        if reserve == 0 {
+            shares.push(share);
+        }
 
-                    *count -= n;
-                    reserve -= n
-                }
-            });
+        return;
+    };
+
+    for count in 0..=reserve.min(left[resource]) {
+        let mut share = share;
+        share[resource] += count;
+
+        split(rest, reserve - count, left, share, shares);
+    }
+}
+
+/// What each discount takes off the resources for the lowest price, by the index of the discount.
+///
+/// Discounts overlap, a greedy pass can spend a wide one on a resource a narrow one covers as well,
+/// so every split is tried. An unspent reserve never lowers the price, only full ones are searched.
+pub fn assign_discounts(discounts: &[&Discount], resources: Resources, price: &ResourceMap<Coins>) -> Vec<Resources> {
+    let mut best = (0, vec![Resources::new(); discounts.len()]);
+
+    search(discounts, resources, price, &mut vec![], &mut best);
+
+    best.1
+}
+
+fn search(
+    discounts: &[&Discount],
+    left: Resources,
+    price: &ResourceMap<Coins>,
+    taken: &mut Vec<Resources>,
+    best: &mut (u32, Vec<Resources>),
+) {
+    let Some((discount, rest)) = discounts.split_first() else {
+        let saved = taken.iter()
+            .flat_map(|share| share.iter())
+            .map(|(r, count)| price[&r] as u32 * count as u32)
+            .sum();
+
+        if saved > best.0 {
+            *best = (saved, taken.clone());
+        }
+
+        return;
+    };
+
+    for share in discount.get_shares(&left) {
+        let mut next = left;
+        next.iter_mut().for_each(|(r, count)| *count -= share[&r]);
+
+        taken.push(share);
+        search(rest, next, price, taken, best);
+        taken.pop();
     }
 }
 
 #[derive(Eq, PartialEq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Resource {
     Clay = 1,
     Wood,
@@ -85,15 +136,137 @@ pub type Coins = u8;
 
 pub type PriceList<T> = HashMap<T, Coins>;
 
-pub type Resources = HashMap<Resource, u8>;
+/// Owned unit a discount or a fixed resource price comes from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PriceSource {
+    Building(building::Id),
+    Wonder(wonder::Id),
+    Token(token::Id),
+    Decree(decree::Id),
+}
+
+/// How a price is made up for the player who pays it.
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceQuote {
+    // printed on the card
+    pub coins: Coins,
+    // resources of the cost only
+    pub resources: Vec<ResourceQuote>,
+    // free by a chain, nothing else is paid
+    pub chain: bool,
+    pub total: Coins,
+    // part of the total the opponent gets through the Economy token
+    pub to_opponent: Coins,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourceQuote {
+    pub resource: Resource,
+    pub required: u8,
+    pub produced: u8,
+    // in the order discounts are applied, the source is unknown when nothing owned grants it
+    pub discounted: Vec<(Option<PriceSource>, u8)>,
+    pub bought: u8,
+    pub unit_price: Coins,
+    // trading at the fixed price instead of the one set by the opponent production
+    pub fixed_by: Option<PriceSource>,
+}
+
+/// Value per resource kept in a fixed array, resources left out are zero.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ResourceMap<T>([T; Resource::ALL.len()]);
+
+pub type Resources = ResourceMap<u8>;
+
+impl<T: Copy + Default> ResourceMap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filled(value: T) -> Self {
+        Self([value; Resource::ALL.len()])
+    }
+
+    // returns the previous value
+    pub fn insert(&mut self, r: Resource, value: T) -> T {
+        std::mem::replace(&mut self[&r], value)
+    }
+
+    // every resource in the order of Resource::ALL
+    pub fn iter(&self) -> impl Iterator<Item = (Resource, T)> + '_ {
+        Resource::ALL.into_iter().zip(self.0.iter().copied())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Resource, &mut T)> {
+        Resource::ALL.into_iter().zip(self.0.iter_mut())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = T> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+impl<T> Index<&Resource> for ResourceMap<T> {
+    type Output = T;
+
+    fn index(&self, r: &Resource) -> &T {
+        &self.0[*r as usize - 1]
+    }
+}
 
-#[derive(Default, Clone)]
+impl<T> IndexMut<&Resource> for ResourceMap<T> {
+    fn index_mut(&mut self, r: &Resource) -> &mut T {
+        &mut self.0[*r as usize - 1]
+    }
+}
+
+impl<T: Copy + Default, const N: usize> From<[(Resource, T); N]> for ResourceMap<T> {
+    fn from(items: [(Resource, T); N]) -> Self {
+        let mut map = Self::default();
+
+        for (r, value) in items {
+            map[&r] = value;
+        }
+
+        map
+    }
+}
+
+// same format as a map of resources, zero values are left out
+#[cfg(feature = "serde")]
+impl<T: Copy + Default + PartialEq + serde::Serialize> serde::Serialize for ResourceMap<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter().filter(|(_, value)| *value != T::default()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Copy + Default + serde::Deserialize<'de>> serde::Deserialize<'de> for ResourceMap<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = HashMap::<Resource, T>::deserialize(deserializer)?;
+        let mut map = Self::default();
+
+        for (r, value) in items {
+            map[&r] = value;
+        }
+
+        Ok(map)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct Cost {
     pub coins: Coins,
     pub resources: Resources,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Bonus {
     Resources = 1,
     RawMaterials,
@@ -106,4 +279,107 @@ pub enum Bonus {
     Coin,
 }
 
-pub type Points = u8;
\ No newline at end of file
+pub type Points = u8;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{get_all_buildings, get_all_wonders};
+
+    // any amount of any discount on any resource, spent or not
+    fn brute_force(discounts: &[&Discount], left: Resources, price: &ResourceMap<Coins>) -> Coins {
+        let Some((discount, rest)) = discounts.split_first() else {
+            return left.iter().map(|(r, count)| price[&r] * count).sum();
+        };
+
+        let mut best = brute_force(rest, left, price);
+
+        for (r, count) in left.iter() {
+            if count > 0 && discount.count > 0 && discount.resources.contains(&r) {
+                let mut next = left;
+                next[&r] -= 1;
+
+                let discount = Discount {
+                    count: discount.count - 1,
+                    ..(*discount).clone()
+                };
+
+                let mut discounts = vec![&discount];
+                discounts.extend(rest);
+
+                best = best.min(brute_force(&discounts, next, price));
+            }
+        }
+
+        best
+    }
+
+    fn price_of(discounts: &[&Discount], resources: Resources, price: &ResourceMap<Coins>) -> Coins {
+        let shares = assign_discounts(discounts, resources, price);
+
+        for (discount, share) in discounts.iter().zip(&shares) {
+            assert!(share.values().sum::<u8>() <= discount.count);
+            assert!(share.iter().all(|(r, count)| count == 0 || discount.resources.contains(&r)));
+        }
+
+        resources.iter()
+            .map(|(r, count)| price[&r] * (count - shares.iter().map(|share| share[&r]).sum::<u8>()))
+            .sum()
+    }
+
+    #[test]
+    fn greedy_overpays() {
+        let price = ResourceMap::from([(Resource::Clay, 4), (Resource::Glass, 3)]);
+        let any = Discount { scope: PayScope::Global, resources: Resource::ALL.to_vec(), count: 1 };
+        let raw = Discount { scope: PayScope::Global, resources: Resource::RAW_MATERIALS.to_vec(), count: 1 };
+        let resources = Resources::from([(Resource::Clay, 1), (Resource::Glass, 1)]);
+
+        // the wide discount goes first and takes the most expensive resource
+        assert_eq!(0, price_of(&[&any, &raw], resources, &price));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let kinds = [
+            Discount { scope: PayScope::Global, resources: Resource::RAW_MATERIALS.to_vec(), count: 1 },
+            Discount { scope: PayScope::Global, resources: Resource::MANUFACTURED_GOODS.to_vec(), count: 1 },
+            Discount { scope: PayScope::Global, resources: Resource::ALL.to_vec(), count: 1 },
+            Discount { scope: PayScope::Global, resources: Resource::ALL.to_vec(), count: 2 },
+        ];
+        let prices = [
+            ResourceMap::filled(2),
+            ResourceMap::from([(Resource::Clay, 1), (Resource::Wood, 4), (Resource::Stone, 3), (Resource::Glass, 5), (Resource::Papyrus, 2)]),
+            ResourceMap::from([(Resource::Clay, 6), (Resource::Wood, 2), (Resource::Stone, 1), (Resource::Glass, 1), (Resource::Papyrus, 4)]),
+        ];
+
+        let mut costs = get_all_buildings().values()
+            .map(|unit| unit.cost.resources)
+            .chain(get_all_wonders().values().map(|unit| unit.cost.resources))
+            .collect::<Vec<_>>();
+        costs.sort_by_key(|resources| resources.values().collect::<Vec<_>>());
+        costs.dedup();
+
+        // up to three of a kind, as many as the cards and decrees may grant
+        for mask in 0..4usize.pow(kinds.len() as u32) {
+            let discounts = kinds.iter()
+                .enumerate()
+                .flat_map(|(i, kind)| std::iter::repeat_n(kind, mask / 4usize.pow(i as u32) % 4))
+                .collect::<Vec<_>>();
+
+            if discounts.len() > 4 {
+                continue;
+            }
+
+            for price in &prices {
+                for resources in &costs {
+                    assert_eq!(
+                        brute_force(&discounts, *resources, price),
+                        price_of(&discounts, *resources, price),
+                        "{:?} {:?}",
+                        discounts,
+                        resources,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,30 +1,46 @@
-use std::cmp::{max, min};
+use std::cmp::max;
 use std::fmt::{Debug};
 use crate::{
     prelude::*,
     building,
+    conspiracy,
+    divinity,
+    pantheon,
     token,
     state,
+    wonder,
     player::Finisher,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Effect {
+    ActivateDivinity,
     Chain(building::Id),
     Coins(Coins),
     CoinsFor(Bonus, Coins),
+    Conspire,
+    ConstructDiscardedWonder,
+    CopyScientificSymbol,
     DestructBuilding(building::Kind),
+    DestructWonder,
+    DiscardLayoutBuilding,
     DiscardRewardAdjuster,
     Discounter {
         scope: PayScope,
         resources: Vec<Resource>,
         count: u8,
     },
+    DivinityDiscount(Coins),
     Fine(Coins),
     FixedResourcePrice(Vec<Resource>),
+    GrandTemple,
     Guild(Bonus, Coins, Points),
+    Loot,
     Mathematics,
     Military(u8, bool),
+    MilitaryFine(Coins),
+    Minerva,
     PickBoardToken,
     PickDiscardedBuilding,
     PickRandomToken,
@@ -32,23 +48,70 @@ pub enum Effect {
     PickTopLineBuilding,
     PlayAgain,
     Points(u8),
+    RemoveInfluence(u8),
     Resource(Resource, u8),
     Science(ScientificSymbol),
+    Senate(u8),
+    StealBuilding(Vec<building::Kind>),
+    StealWonder,
 }
 
 impl Effect {
     pub fn apply(&self, s: &mut State) {
         match *self {
+            // divinities the opponent placed face down in Age I are unknown
+            Self::ActivateDivinity => {
+                let divinities = s.pantheon.as_ref()
+                    .map(|p| p.board.view_for(s.players.me).divinities())
+                    .unwrap_or_default();
+
+                if !divinities.is_empty() {
+                    s.queue_post_effect(PostEffect::ActivateDivinity(s.players.me, divinities));
+                }
+            }
+
             Self::Chain(building) => {
                 s.me_mut().chains.push(building);
             }
 
             Self::Coins(coins) => {
-                s.me_mut().coins += coins;
+                s.add_coins(s.players.me, coins);
             }
 
             Self::CoinsFor(bonus, coins) => {
-                s.me_mut().coins += s.me().bonus_rate(bonus) * coins;
+                s.add_coins(s.players.me, s.me().bonus_rate(bonus) * coins);
+            }
+
+            Self::Conspire => {
+                let me = s.players.me;
+                let has_cubes = s.get_influence_supply(me) > 0;
+
+                if let Some(agora) = s.agora.as_mut() {
+                    let conspiracies = agora.draw();
+
+                    if has_cubes || !conspiracies.is_empty() {
+                        s.queue_post_effect(PostEffect::Conspire(me, conspiracies));
+                    }
+                }
+            }
+
+            Self::ConstructDiscardedWonder => {
+                let has_free_wonder = s.me().wonders.iter().any(|(_, b)| b.is_none())
+                    && get_constructed_wonders_count(s) < WONDERS_CONSTRUCT_LIMIT as usize;
+
+                if has_free_wonder && !s.buildings.discarded.is_empty() {
+                    s.queue_post_effect(PostEffect::ConstructDiscardedWonder(s.players.me, s.buildings.discarded.clone()));
+                }
+            }
+
+            Self::CopyScientificSymbol => {
+                let symbols = s.enemy().scientific_symbols.iter()
+                    .map(|(symbol, _)| *symbol)
+                    .collect::<Vec<_>>();
+
+                if !symbols.is_empty() {
+                    s.queue_post_effect(PostEffect::PickScientificSymbol(s.players.me, symbols));
+                }
             }
 
             Self::DestructBuilding(kind) => {
@@ -58,7 +121,26 @@ impl Effect {
                     return;
                 }
 
-                s.post_effects.push(PostEffect::DestructBuilding(s.players.me, buildings));
+                s.queue_post_effect(PostEffect::DestructBuilding(s.players.me, buildings));
+            }
+
+            Self::DestructWonder => {
+                let wonders = s.enemy().wonders.iter()
+                    .filter_map(|(w, b)| b.map(|_| *w))
+                    .collect::<Vec<_>>();
+
+                if !wonders.is_empty() {
+                    s.queue_post_effect(PostEffect::DestructWonder(s.players.me, wonders));
+                }
+            }
+
+            Self::DiscardLayoutBuilding => {
+                let mut buildings = s.deck.get_playable_buildings().into_iter().collect::<Vec<_>>();
+                buildings.sort();
+
+                if !buildings.is_empty() {
+                    s.queue_post_effect(PostEffect::DiscardLayoutBuilding(s.players.me, buildings));
+                }
             }
 
             Self::DiscardRewardAdjuster => {
@@ -73,19 +155,35 @@ impl Effect {
                 });
             }
 
+            Self::DivinityDiscount(coins) => {
+                s.me_mut().bank.divinity_discount += coins;
+            }
+
             Self::Fine(coins) => {
-                s.enemy_mut().coins -= min(coins, s.enemy().coins);
+                s.take_coins(s.players.enemy, coins);
             }
 
             Self::FixedResourcePrice(ref resources) => {
                 resources.iter()
                     .for_each(|resource| {
-                        *s.me_mut().bank.resource_price.get_mut(resource).unwrap() = FIXED_RESOURCE_PRICE;
+                        s.set_resource_price(s.players.me, *resource, FIXED_RESOURCE_PRICE);
                     });
             }
 
             Self::Guild(bonus, coins, ..) => {
-                s.me_mut().coins += get_guild_rate(s, bonus) * coins;
+                s.add_coins(s.players.me, get_guild_rate(s, bonus) * coins);
+            }
+
+            // next zone with effects of the track in play, senate actions with agora
+            Self::Loot => {
+                let zones = s.conflict_track().zones();
+                let zone = (s.me().track.max_zone + 1..zones.len())
+                    .find(|ind| !zones[*ind].effects.is_empty());
+
+                if let Some(zone) = zone {
+                    s.me_mut().track.max_zone = zone;
+                    s.apply_zone(zone);
+                }
             }
 
             Self::Military(power, use_strategy_token) => {
@@ -95,10 +193,16 @@ impl Effect {
                     power += 1;
                 }
 
-                let (fine, supremacy) = s.move_conflict_pawn(power);
+                let (zone, supremacy) = s.move_conflict_pawn(power);
 
-                if fine > 0 {
-                    s.enemy_mut().coins -= min(fine, s.enemy_mut().coins);
+                s.emit(GameEvent::ConflictPawnMoved {
+                    player: s.players.me,
+                    power,
+                    pos: s.me().track.pos as i8 - s.enemy().track.pos as i8,
+                });
+
+                if let Some(zone) = zone {
+                    s.apply_zone(zone);
                 }
 
                 if supremacy {
@@ -106,31 +210,36 @@ impl Effect {
                 }
             }
 
+            Self::MilitaryFine(coins) => {
+                let coins = s.take_coins(s.players.enemy, coins);
+                s.emit(GameEvent::MilitaryFine { player: s.players.enemy, coins });
+            }
+
+            Self::Minerva => {
+                s.me_mut().track.guarded = true;
+            }
+
             Self::PickBoardToken => {
                 let tokens = s.tokens.iter().flatten().cloned().collect::<Vec<_>>();
 
                 if !tokens.is_empty() {
-                    s.post_effects.push(PostEffect::PickBoardToken(s.players.me, tokens));
+                    s.queue_post_effect(PostEffect::PickBoardToken(s.players.me, tokens));
                 }
             }
 
-            Self::PickDiscardedBuilding => {
-                if !s.buildings.discarded.is_empty() {
-                    s.post_effects.push(PostEffect::PickDiscardedBuilding(s.players.me, s.buildings.discarded.clone()));
-                }
+            Self::PickDiscardedBuilding if !s.buildings.discarded.is_empty() => {
+                s.queue_post_effect(PostEffect::PickDiscardedBuilding(s.players.me, s.buildings.discarded.clone()));
             }
 
-            Self::PickRandomToken => {
-                if !s.random_units.tokens.is_empty() {
-                    s.post_effects.push(PostEffect::PickRandomToken(s.players.me, s.random_units.tokens.clone()));
-                }
+            Self::PickRandomToken if !s.random_units.tokens.is_empty() => {
+                s.queue_post_effect(PostEffect::PickRandomToken(s.players.me, s.random_units.tokens.clone()));
             }
 
             Self::PickReturnedBuildings => {
                 let returned_buildings = s.deck.get_returned_buildings();
 
                 if !returned_buildings.is_empty() {
-                    s.post_effects.push(PostEffect::PickReturnedBuildings(s.players.me, returned_buildings));
+                    s.queue_post_effect(PostEffect::PickReturnedBuildings(s.players.me, returned_buildings));
                 }
             }
 
@@ -138,7 +247,7 @@ impl Effect {
                 let top_line_buildings = s.deck.get_top_line_buildings();
 
                 if !top_line_buildings.is_empty() {
-                    s.post_effects.push(PostEffect::PickTopLineBuilding(s.players.me, top_line_buildings));
+                    s.queue_post_effect(PostEffect::PickTopLineBuilding(s.players.me, top_line_buildings));
                 }
             }
 
@@ -146,11 +255,15 @@ impl Effect {
                 s.play_again = true;
             }
 
+            Self::RemoveInfluence(count) if s.agora.is_some() && !s.get_removal_actions().is_empty() => {
+                s.queue_post_effect(PostEffect::RemoveInfluence(s.players.me, count));
+            }
+
             Self::Resource(r, count) => {
-                *s.me_mut().resources.get_mut(&r).unwrap() += count;
+                s.me_mut().resources[&r] += count;
 
                 if !s.enemy().bank.has_fixed_resource_price(&r) {
-                    *s.enemy_mut().bank.resource_price.get_mut(&r).unwrap() = DEFAULT_RESOURCE_PRICE + s.me().resources[&r];
+                    s.set_resource_price(s.players.enemy, r, DEFAULT_RESOURCE_PRICE + s.me().resources[&r]);
                 }
             }
 
@@ -170,23 +283,93 @@ impl Effect {
                     s.me_mut().scientific_symbols.push((symbol, 1));
                 }
 
+                s.emit(GameEvent::ScienceSymbolGained { player: s.players.me, symbol });
+
                 if s.me().scientific_symbols.len() == DIFFERENT_SCIENTIFIC_SYMBOLS_FOR_SUPREMACY as usize {
                     state::over(s, Finisher::Winner(s.players.me), Victory::ScienceSupremacy);
                 }
             }
 
+            Self::Senate(count) if s.agora.is_some() && count > 0 => {
+                s.queue_post_effect(PostEffect::Senate(s.players.me, count));
+            }
+
+            Self::StealBuilding(ref kinds) => {
+                let buildings = s.enemy().buildings.iter()
+                    .filter(|bid| kinds.contains(&get_building(bid).kind))
+                    .collect::<Vec<_>>();
+
+                if !buildings.is_empty() {
+                    s.queue_post_effect(PostEffect::StealBuilding(s.players.me, buildings));
+                }
+            }
+
+            Self::StealWonder => {
+                let wonders = s.enemy().wonders.iter()
+                    .filter_map(|(w, b)| if b.is_none() { Some(*w) } else { None })
+                    .collect::<Vec<_>>();
+
+                if !wonders.is_empty() {
+                    s.queue_post_effect(PostEffect::StealWonder(s.players.me, wonders));
+                }
+            }
+
             _ => (),
         };
     }
 
+    // on behalf of the owner, the unit has already left the city
     pub fn rollback(&self, s: &mut State) {
-        if let Effect::Resource(rid, count) = self {
-            let current = s.me().resources[rid];
-            *s.me_mut().resources.get_mut(rid).unwrap() = min(current - count, 0);
+        match *self {
+            Self::Chain(building) => {
+                let chains = &mut s.me_mut().chains;
 
-            if !s.me().bank.has_fixed_resource_price(rid) {
-                *s.me_mut().bank.resource_price.get_mut(rid).unwrap() = DEFAULT_RESOURCE_PRICE + s.enemy().resources[rid];
+                if let Some(ind) = chains.iter().position(|b| *b == building) {
+                    chains.remove(ind);
+                }
             }
+
+            Self::DiscardRewardAdjuster => {
+                s.me_mut().bank.discard_reward -= 1;
+            }
+
+            Self::Discounter { scope, ref resources, count } => {
+                let discount = Discount {
+                    scope,
+                    resources: resources.clone(),
+                    count,
+                };
+                let discounts = &mut s.me_mut().bank.discounts;
+
+                if let Some(ind) = discounts.iter().position(|d| *d == discount) {
+                    discounts.remove(ind);
+                }
+            }
+
+            Self::DivinityDiscount(coins) => {
+                s.me_mut().bank.divinity_discount -= coins;
+            }
+
+            // another owned unit may still fix the price
+            Self::FixedResourcePrice(ref resources) => {
+                for resource in resources {
+                    if state::get_fixed_price_source(s, *resource).is_none() {
+                        s.set_resource_price(s.players.me, *resource, DEFAULT_RESOURCE_PRICE + s.enemy().resources[resource]);
+                    }
+                }
+            }
+
+            // production sets the price for the opponent
+            Self::Resource(rid, count) => {
+                let current = s.me().resources[&rid];
+                s.me_mut().resources[&rid] = current.saturating_sub(count);
+
+                if !s.enemy().bank.has_fixed_resource_price(&rid) {
+                    s.set_resource_price(s.players.enemy, rid, DEFAULT_RESOURCE_PRICE + s.me().resources[&rid]);
+                }
+            }
+
+            _ => (),
         }
     }
 
@@ -196,6 +379,11 @@ impl Effect {
                 get_guild_rate(s, bonus) * points
             }
 
+            Self::GrandTemple => {
+                let temples = building::count_by_kind(&s.me().buildings, building::Kind::GrandTemple);
+                pantheon::GRAND_TEMPLE_POINTS[temples as usize]
+            }
+
             Self::Mathematics => {
                 s.me().tokens.len() as u8 * 3
             }
@@ -209,56 +397,128 @@ impl Effect {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PostEffect {
+    ActivateDivinity(Nickname, Vec<divinity::Id>),
+    // drawn conspiracies, a cube can be placed instead
+    Conspire(Nickname, Vec<conspiracy::Id>),
+    ConstructDiscardedWonder(Nickname, Vec<building::Id>),
     DestructBuilding(Nickname, Vec<building::Id>),
+    DestructWonder(Nickname, Vec<wonder::Id>),
+    DiscardLayoutBuilding(Nickname, Vec<building::Id>),
     PickBoardToken(Nickname, Vec<token::Id>),
     PickDiscardedBuilding(Nickname, Vec<building::Id>),
+    PickDivinity(Nickname, Vec<divinity::Id>),
     PickRandomToken(Nickname, Vec<token::Id>),
     PickReturnedBuildings(Nickname, Vec<building::Id>),
+    PickScientificSymbol(Nickname, Vec<ScientificSymbol>),
     PickTopLineBuilding(Nickname, Vec<building::Id>),
+    // number of actions
+    RemoveInfluence(Nickname, u8),
+    Senate(Nickname, u8),
+    StealBuilding(Nickname, Vec<building::Id>),
+    StealWonder(Nickname, Vec<wonder::Id>),
 }
 
 impl PostEffect {
     pub fn apply(self, s: &mut State) {
+        s.set_phase(self.phase());
+        s.players.set_turn(self.actor());
+
         match self {
-            Self::DestructBuilding(actor, buildings) => {
-                s.phase = Phase::DestructBuildingSelection;
-                s.players.set_turn(actor);
+            Self::ConstructDiscardedWonder(_, buildings)
+            | Self::DestructBuilding(_, buildings)
+            | Self::DiscardLayoutBuilding(_, buildings)
+            | Self::PickDiscardedBuilding(_, buildings)
+            | Self::PickReturnedBuildings(_, buildings)
+            | Self::PickTopLineBuilding(_, buildings)
+            | Self::StealBuilding(_, buildings) => {
                 s.interactive_units.buildings = buildings;
             }
 
-            Self::PickBoardToken(actor, tokens) => {
-                s.phase = Phase::BoardTokenSelection;
-                s.players.set_turn(actor);
-                s.interactive_units.tokens = tokens;
+            Self::DestructWonder(_, wonders)
+            | Self::StealWonder(_, wonders) => {
+                s.interactive_units.wonders = wonders.into_iter().map(Some).collect();
             }
 
-            Self::PickDiscardedBuilding(actor, buildings) => {
-                s.phase = Phase::DiscardedBuildingSelection;
-                s.players.set_turn(actor);
-                s.interactive_units.buildings = buildings;
+            Self::ActivateDivinity(_, divinities)
+            | Self::PickDivinity(_, divinities) => {
+                s.interactive_units.divinities = divinities;
             }
 
-            Self::PickRandomToken(actor, tokens) => {
-                s.phase = Phase::RandomTokenSelection;
-                s.players.set_turn(actor);
+            Self::PickBoardToken(_, tokens)
+            | Self::PickRandomToken(_, tokens) => {
                 s.interactive_units.tokens = tokens;
             }
 
-            Self::PickReturnedBuildings(actor, buildings) => {
-                s.phase = Phase::ReturnedBuildingSelection;
-                s.players.set_turn(actor);
-                s.interactive_units.buildings = buildings;
+            Self::PickScientificSymbol(_, symbols) => {
+                s.interactive_units.symbols = symbols;
             }
 
-            Self::PickTopLineBuilding(actor, buildings) => {
-                s.phase = Phase::TopLineBuildingSelection;
-                s.players.set_turn(actor);
-                s.interactive_units.buildings = buildings;
+            Self::Conspire(_, conspiracies) => {
+                s.interactive_units.conspiracies = conspiracies;
+            }
+
+            Self::RemoveInfluence(_, actions)
+            | Self::Senate(_, actions) => {
+                if let Some(agora) = s.agora.as_mut() {
+                    agora.actions = actions;
+                }
             }
         }
     }
+
+    pub fn actor(&self) -> Nickname {
+        match *self {
+            Self::ActivateDivinity(actor, _)
+            | Self::Conspire(actor, _)
+            | Self::ConstructDiscardedWonder(actor, _)
+            | Self::DestructBuilding(actor, _)
+            | Self::DestructWonder(actor, _)
+            | Self::DiscardLayoutBuilding(actor, _)
+            | Self::PickBoardToken(actor, _)
+            | Self::PickDiscardedBuilding(actor, _)
+            | Self::PickDivinity(actor, _)
+            | Self::PickRandomToken(actor, _)
+            | Self::PickReturnedBuildings(actor, _)
+            | Self::PickScientificSymbol(actor, _)
+            | Self::PickTopLineBuilding(actor, _)
+            | Self::RemoveInfluence(actor, _)
+            | Self::Senate(actor, _)
+            | Self::StealBuilding(actor, _)
+            | Self::StealWonder(actor, _) => actor,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        match self {
+            Self::ActivateDivinity(..) => Phase::DivinityActivationSelection,
+            Self::Conspire(..) => Phase::ConspiracySelection,
+            Self::ConstructDiscardedWonder(..) => Phase::DiscardedWonderSelection,
+            Self::DestructBuilding(..) => Phase::DestructBuildingSelection,
+            Self::DestructWonder(..) => Phase::DestructWonderSelection,
+            Self::DiscardLayoutBuilding(..) => Phase::LayoutBuildingSelection,
+            Self::PickBoardToken(..) => Phase::BoardTokenSelection,
+            Self::PickDiscardedBuilding(..) => Phase::DiscardedBuildingSelection,
+            Self::PickDivinity(..) => Phase::DivinitySelection,
+            Self::PickRandomToken(..) => Phase::RandomTokenSelection,
+            Self::PickReturnedBuildings(..) => Phase::ReturnedBuildingSelection,
+            Self::PickScientificSymbol(..) => Phase::ScientificSymbolSelection,
+            Self::PickTopLineBuilding(..) => Phase::TopLineBuildingSelection,
+            Self::RemoveInfluence(..) => Phase::InfluenceRemovalSelection,
+            Self::Senate(..) => Phase::SenateSelection,
+            Self::StealBuilding(..) => Phase::StealBuildingSelection,
+            Self::StealWonder(..) => Phase::StealWonderSelection,
+        }
+    }
+}
+
+pub(crate) fn get_constructed_wonders_count(s: &State) -> usize {
+    s.me().wonders.iter()
+        .chain(s.enemy().wonders.iter())
+        .filter(|(_, b)| b.is_some())
+        .count()
 }
 
 fn get_guild_rate(s: &State, b: Bonus) -> u8 {
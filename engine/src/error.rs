@@ -0,0 +1,184 @@
+use std::fmt;
+use crate::{
+    prelude::*,
+    agora,
+    building,
+    conspiracy,
+    divinity,
+    token,
+    wonder,
+};
+#[cfg(feature = "definitions")]
+use crate::definition::UnitId;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error {
+    GameNotStarted,
+    GameOver,
+    WrongPhase {
+        expected: Phase,
+        actual: Phase,
+    },
+    // players act on their turn and resign only for themselves
+    NotYourTurn,
+    // timeouts come from the clock, players can not report them
+    TimeoutByClockOnly,
+    UnknownPlayer(Nickname),
+    BuildingNotPlayable(building::Id),
+    WonderNotOwned(wonder::Id),
+    WonderAlreadyBuilt(wonder::Id),
+    WonderLimitReached,
+    // not on the pantheon board or the pantheon is not open yet
+    DivinityNotAvailable(divinity::Id),
+    // not on the pantheon board, the pantheon is not open yet or the piles are empty
+    GateNotAvailable,
+    // all cubes of the player are in the senate
+    NoInfluenceCubes,
+    ConspiracyNotPrepared(conspiracy::Id),
+    NotEnoughCoins {
+        need: Coins,
+        have: Coins,
+    },
+    // chosen unit is not among the offered ones
+    UnknownChoice,
+    InvalidSetup(SetupError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GameNotStarted => write!(f, "game is not started"),
+            Self::GameOver => write!(f, "game is over"),
+            Self::WrongPhase { expected, actual } => write!(f, "action is allowed in phase {:?}, current phase is {:?}", expected, actual),
+            Self::NotYourTurn => write!(f, "not your turn"),
+            Self::TimeoutByClockOnly => write!(f, "only the clock can run out the time"),
+            Self::UnknownPlayer(p) => write!(f, "player {} is not seated", p),
+            Self::BuildingNotPlayable(id) => write!(f, "building {:?} is not playable", id),
+            Self::WonderNotOwned(id) => write!(f, "wonder {:?} is not owned", id),
+            Self::WonderAlreadyBuilt(id) => write!(f, "wonder {:?} is already built", id),
+            Self::WonderLimitReached => write!(f, "{} wonders are already built", WONDERS_CONSTRUCT_LIMIT),
+            Self::DivinityNotAvailable(id) => write!(f, "divinity {:?} is not available", id),
+            Self::GateNotAvailable => write!(f, "gate is not available"),
+            Self::NoInfluenceCubes => write!(f, "no influence cubes left"),
+            Self::ConspiracyNotPrepared(id) => write!(f, "conspiracy {:?} is not prepared", id),
+            Self::NotEnoughCoins { need, have } => write!(f, "not enough coins, need {}, have {}", need, have),
+            Self::UnknownChoice => write!(f, "chosen unit is not offered"),
+            Self::InvalidSetup(e) => write!(f, "invalid setup: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidSetup(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SetupError {
+    SamePlayers,
+    WondersCount(usize),
+    DuplicateWonder(wonder::Id),
+    UnknownWonder(wonder::Id),
+    BoardTokensCount(usize),
+    RandomTokensCount(usize),
+    DuplicateToken(token::Id),
+    UnknownToken(token::Id),
+    BuildingsCount(Age, usize),
+    // building placed in the deck of another age
+    WrongAge(building::Id, Age),
+    DuplicateBuilding(building::Id),
+    GuildsCount(usize),
+    TemplesCount(usize),
+    // each mythology pile must hold all its divinities
+    DivinityPiles,
+    MythologyTokens,
+    OfferingTokens,
+    AgoraBuildingsCount(Age, usize),
+    // each chamber must hold its own decree
+    Decrees,
+    Conspiracies,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SamePlayers => write!(f, "players must be different"),
+            Self::WondersCount(n) => write!(f, "expected {} wonders, got {}", WONDER_TOTAL_POOL_SIZE, n),
+            Self::DuplicateWonder(id) => write!(f, "wonder {:?} is repeated", id),
+            Self::UnknownWonder(id) => write!(f, "wonder {:?} is not defined", id),
+            Self::BoardTokensCount(n) => write!(f, "expected {} board tokens, got {}", STARTING_TOKENS_COUNT, n),
+            Self::RandomTokensCount(n) => write!(f, "expected {} random tokens, got {}", RANDOM_TOKENS_COUNT, n),
+            Self::DuplicateToken(id) => write!(f, "token {:?} is repeated", id),
+            Self::UnknownToken(id) => write!(f, "token {:?} is not defined", id),
+            Self::BuildingsCount(age, n) => write!(f, "expected {} buildings in age {:?}, got {}", DECK_LIMIT, age, n),
+            Self::WrongAge(id, age) => write!(f, "building {:?} does not belong to age {:?}", id, age),
+            Self::DuplicateBuilding(id) => write!(f, "building {:?} is repeated", id),
+            Self::GuildsCount(n) => write!(f, "unexpected number of guilds in the last age: {}", n),
+            Self::TemplesCount(n) => write!(f, "unexpected number of grand temples in the last age: {}", n),
+            Self::DivinityPiles => write!(f, "divinity piles do not match mythologies"),
+            Self::MythologyTokens => write!(f, "mythology tokens must cover every mythology on face down slots of age I"),
+            Self::OfferingTokens => write!(f, "offering tokens must be placed on face down slots of age II"),
+            Self::AgoraBuildingsCount(age, n) => write!(f, "unexpected number of politicians and conspirators in age {:?}: {}", age, n),
+            Self::Decrees => write!(f, "expected {} different decrees", agora::CHAMBERS_COUNT),
+            Self::Conspiracies => write!(f, "conspiracies pile must hold every conspiracy once"),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+#[cfg(feature = "definitions")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefinitionError {
+    // malformed file or unknown field, id or effect
+    Parse(String),
+    DuplicateUnit(UnitId),
+    // chained unit is neither built-in nor defined
+    UnknownUnit(UnitId),
+    // guilds and grand temples belong to the last age
+    WrongAge(building::Id, Age),
+    // chained building must come in a later age
+    WrongChain(building::Id, building::Id),
+    // too few buildings left to deal a deck
+    BuildingsCount(Age, usize),
+    AgoraBuildingsCount(Age, usize),
+    LastAgeCount(building::Kind, usize),
+    // more ids than a city can hold
+    TooManyUnits(usize),
+    InvalidCost(UnitId),
+    InvalidEffect(UnitId, Effect),
+    // mythology is fixed by the id like the age of a building
+    WrongMythology(divinity::Id, divinity::Mythology),
+    AlreadyInstalled,
+    // units must not change under a game
+    GamePrepared,
+}
+
+#[cfg(feature = "definitions")]
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "malformed definitions: {}", e),
+            Self::DuplicateUnit(id) => write!(f, "unit {:?} is repeated", id),
+            Self::UnknownUnit(id) => write!(f, "unit {:?} is not defined", id),
+            Self::WrongAge(id, age) => write!(f, "building {:?} must be in age {:?}", id, age),
+            Self::WrongChain(id, chain) => write!(f, "building {:?} can not chain {:?} of the same or earlier age", id, chain),
+            Self::BuildingsCount(age, n) => write!(f, "age {:?} has {} buildings, too few for its deck", age, n),
+            Self::AgoraBuildingsCount(age, n) => write!(f, "age {:?} has {} politicians and conspirators, at least {} expected", age, n, agora::AGORA_BUILDINGS_LIMIT),
+            Self::LastAgeCount(kind, n) => write!(f, "{} buildings of kind {:?}, at least {} expected", n, kind, GUILDS_LIMIT),
+            Self::TooManyUnits(n) => write!(f, "{} units of a kind, at most {} expected", n, crate::bitset::CAPACITY),
+            Self::InvalidCost(id) => write!(f, "unit {:?} has a resource of zero count", id),
+            Self::InvalidEffect(id, effect) => write!(f, "unit {:?} has an empty effect {:?}", id, effect),
+            Self::WrongMythology(id, m) => write!(f, "divinity {:?} must stay of mythology {:?}", id, m),
+            Self::AlreadyInstalled => write!(f, "definitions are already installed"),
+            Self::GamePrepared => write!(f, "definitions can not change once a game is prepared"),
+        }
+    }
+}
+
+#[cfg(feature = "definitions")]
+impl std::error::Error for DefinitionError {}
@@ -0,0 +1,206 @@
+use crate::{
+    building,
+    conspiracy,
+    decree,
+    divinity::{self, Mythology},
+    prelude::*,
+    state::Finish,
+    token,
+    wonder,
+};
+
+/// Atomic change of the game state, `Action::apply` returns them in the order they happened.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent {
+    PhaseChanged(Phase),
+    AgeStarted(Age),
+    CoinsChanged {
+        player: Nickname,
+        delta: i16,
+        coins: Coins,
+    },
+    // pos is counted from the center towards the enemy capital of player, negative on player side
+    ConflictPawnMoved {
+        player: Nickname,
+        power: u8,
+        pos: i8,
+    },
+    MilitaryFine {
+        player: Nickname,
+        coins: Coins,
+    },
+    ScienceSymbolGained {
+        player: Nickname,
+        symbol: ScientificSymbol,
+    },
+    ResourcePriceChanged {
+        player: Nickname,
+        resource: Resource,
+        price: Coins,
+    },
+    // selection phase the player will be asked for, units are not exposed as they could be hidden
+    PostEffectQueued {
+        player: Nickname,
+        phase: Phase,
+    },
+    WonderPicked {
+        player: Nickname,
+        wonder: wonder::Id,
+    },
+    WonderConstructed {
+        player: Nickname,
+        wonder: wonder::Id,
+        building: building::Id,
+    },
+    BuildingConstructed {
+        player: Nickname,
+        building: building::Id,
+    },
+    BuildingDiscarded {
+        player: Nickname,
+        building: building::Id,
+    },
+    // player is the owner of the destroyed building
+    BuildingDestroyed {
+        player: Nickname,
+        building: building::Id,
+    },
+    BuildingStolen {
+        player: Nickname,
+        building: building::Id,
+    },
+    // player is the owner of the destroyed wonder
+    WonderDestroyed {
+        player: Nickname,
+        wonder: wonder::Id,
+    },
+    WonderStolen {
+        player: Nickname,
+        wonder: wonder::Id,
+    },
+    TokenPicked {
+        player: Nickname,
+        token: token::Id,
+    },
+    MythologyTokenTaken {
+        player: Nickname,
+        mythology: Mythology,
+    },
+    OfferingTokenTaken {
+        player: Nickname,
+        discount: Coins,
+    },
+    // face down, the divinity is known to the player who placed it
+    DivinityPlaced {
+        player: Nickname,
+        space: usize,
+    },
+    DivinityRevealed {
+        divinity: divinity::Id,
+        space: usize,
+    },
+    DivinityActivated {
+        player: Nickname,
+        divinity: divinity::Id,
+    },
+    GatePlaced {
+        space: usize,
+    },
+    // the divinity to activate is chosen next from the top ones of the piles
+    GateActivated {
+        player: Nickname,
+    },
+    InfluencePlaced {
+        player: Nickname,
+        chamber: usize,
+    },
+    InfluenceMoved {
+        player: Nickname,
+        from: usize,
+        to: usize,
+    },
+    // player is the owner of the removed cube
+    InfluenceRemoved {
+        player: Nickname,
+        chamber: usize,
+    },
+    // none when the chamber has no majority
+    ChamberControlChanged {
+        chamber: usize,
+        player: Option<Nickname>,
+    },
+    DecreeRevealed {
+        chamber: usize,
+        decree: decree::Id,
+    },
+    // prepared conspiracy stays hidden until triggered
+    ConspiracyPrepared {
+        player: Nickname,
+    },
+    ConspiracyTriggered {
+        player: Nickname,
+        conspiracy: conspiracy::Id,
+    },
+    GameFinished(Finish),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        building::Id::*,
+        tests::game_11_actions,
+    };
+
+    fn apply_all(actions: Vec<Action>) -> (State, Vec<GameEvent>) {
+        let mut s = State::default();
+        let mut events = vec![];
+
+        for action in actions {
+            events.extend(action.apply(&mut s).expect("its ok"));
+        }
+
+        (s, events)
+    }
+
+    #[test]
+    fn wonders_selection() {
+        let (s, events) = apply_all(game_11_actions().into_iter().take(9).collect());
+
+        assert_eq!(Some(&GameEvent::PhaseChanged(Phase::WondersSelection)), events.first());
+        assert_eq!(8, events.iter().filter(|e| matches!(e, GameEvent::WonderPicked { .. })).count());
+        assert_eq!(
+            &events[events.len() - 2..],
+            &[GameEvent::PhaseChanged(Phase::Turn), GameEvent::AgeStarted(Age::I)],
+        );
+        assert_eq!(Phase::Turn, s.phase);
+    }
+
+    #[test]
+    fn coins_follow_city() {
+        let (s, events) = apply_all(game_11_actions());
+
+        for p in [1, 2] {
+            let delta = events.iter()
+                .filter_map(|e| match e {
+                    GameEvent::CoinsChanged { player, delta, .. } if *player == p => Some(*delta),
+                    _ => None,
+                })
+                .sum::<i16>();
+
+            assert_eq!(s.cities[&p].coins as i16, STARTING_CITY_COINS as i16 + delta);
+        }
+
+        assert_eq!(Some(&GameEvent::GameFinished(s.finish.clone().unwrap())), events.last());
+        assert_eq!(2, events.iter().filter(|e| matches!(e, GameEvent::AgeStarted(Age::II | Age::III))).count());
+    }
+
+    #[test]
+    fn rejected_action_has_no_events() {
+        let mut s = State::from(game_11_actions().into_iter().take(9).collect()).expect("its ok");
+
+        assert!(Action::DiscardBuilding(Palace).apply(&mut s).is_err());
+        assert!(s.events.is_empty());
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+use crate::{
+    prelude::*,
+    action::Setup,
+    agora,
+    building,
+    clock::{Clock, Millis, TimeControl},
+    deck::Layout,
+    economy::PriceQuote,
+    military::ConflictTrack,
+    pantheon::Board,
+    state::{Finish, Units},
+    view::PlayerView,
+    token,
+    wonder,
+};
+
+/// Public facade of the engine.
+///
+/// Owns the game [`State`] and lets it change only through [`Action`]s,
+/// everything else is exposed read-only. Internals like the deck graph or
+/// the random units pool stay hidden.
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    state: State,
+    clock: Option<Clock>,
+}
+
+impl Game {
+    /// Starts a new game from the given setup.
+    pub fn new(setup: Setup) -> Result<Self, Error> {
+        let mut game = Self::default();
+        Action::Prepare(setup).apply(&mut game.state)?;
+
+        Ok(game)
+    }
+
+    /// Replays an action log, the first action is expected to be [`Action::Prepare`].
+    pub fn from(actions: Vec<Action>) -> Result<Self, Error> {
+        Ok(Self {
+            state: State::from(actions)?,
+            clock: None,
+        })
+    }
+
+    /// Replaces the conflict track of the game, house rules go here.
+    /// The track is serialized with the game as its [`crate::TrackLayout`].
+    pub fn set_conflict_track(&mut self, track: impl ConflictTrack + 'static) {
+        self.state.set_conflict_track(track);
+    }
+
+    /// Applies the move made by `actor`, moves out of turn and timeouts are rejected.
+    /// Returns what has changed, in order.
+    pub fn apply(&mut self, actor: Nickname, action: Action) -> Result<Vec<GameEvent>, Error> {
+        action.apply_by(&mut self.state, actor)
+    }
+
+    /// Starts the clocks of both players, the time of the player on turn runs from `now`.
+    pub fn set_clock(&mut self, control: TimeControl, now: Millis) {
+        self.clock = Some(Clock::new(control, self.players(), now));
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    /// Time left to the player at `now`, None for a game without a clock.
+    pub fn remaining(&self, player: Nickname, now: Millis) -> Option<Millis> {
+        let clock = self.clock.as_ref()?;
+        let running = if player == self.turn() { clock.elapsed(now) } else { 0 };
+
+        Some(clock.remaining(player).saturating_sub(running))
+    }
+
+    /// [`Game::apply`] for a game with a clock, the move is timed at `now`.
+    /// A move made after the time of the player on turn is out ends the game by timeout instead.
+    pub fn apply_at(&mut self, actor: Nickname, action: Action, now: Millis) -> Result<Vec<GameEvent>, Error> {
+        if let Some(events) = self.check_clock(now) {
+            return Ok(events);
+        }
+
+        let turn = self.turn();
+        let events = self.apply(actor, action)?;
+
+        // resigning out of turn does not stop the clock of the player on turn
+        if let Some(clock) = self.clock.as_mut().filter(|_| actor == turn) {
+            clock.punch(turn, now);
+        }
+
+        Ok(events)
+    }
+
+    /// Ends the game with [`Victory::Timeout`] if the player on turn is out of time at `now`.
+    pub fn check_clock(&mut self, now: Millis) -> Option<Vec<GameEvent>> {
+        let clock = self.clock.as_ref()?;
+        let turn = self.turn();
+
+        if matches!(self.phase(), Phase::None | Phase::Over) || !clock.is_flagged(turn, now) {
+            return None;
+        }
+
+        Some(self.apply_trusted(Action::Timeout(turn)).expect("seated player can run out of time"))
+    }
+
+    // history navigation replays moves without time
+    pub(crate) fn restore_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+    }
+
+    // for logs which are already validated
+    pub(crate) fn apply_trusted(&mut self, action: Action) -> Result<Vec<GameEvent>, Error> {
+        action.apply(&mut self.state)
+    }
+
+    /// Every action the player on turn may make right now, except [`Action::Resign`] and [`Action::Timeout`].
+    pub fn legal_actions(&self) -> Vec<Action> {
+        self.state.legal_actions()
+    }
+
+    /// Game as the given player sees it, without hidden cards and tokens.
+    pub fn view_for(&self, player: Nickname) -> PlayerView {
+        self.state.view_for(player)
+    }
+
+    pub fn age(&self) -> Age {
+        self.state.age
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.state.phase
+    }
+
+    /// Player who has to make the next move.
+    pub fn turn(&self) -> Nickname {
+        self.state.players.me
+    }
+
+    /// Seated players, the one who started the game goes first.
+    pub fn players(&self) -> [Nickname; 2] {
+        let players = &self.state.players;
+
+        if players.me == players.starts {
+            [players.me, players.enemy]
+        } else {
+            [players.enemy, players.me]
+        }
+    }
+
+    pub fn city(&self, player: Nickname) -> Option<&City> {
+        self.state.cities.get(&player)
+    }
+
+    /// Pyramid of the current age as clients see it, face down cards are not revealed.
+    pub fn layout(&self) -> &Layout {
+        &self.state.buildings.layout
+    }
+
+    pub fn playable_buildings(&self) -> &HashSet<building::Id> {
+        &self.state.buildings.playable
+    }
+
+    pub fn discarded_buildings(&self) -> &[building::Id] {
+        &self.state.buildings.discarded
+    }
+
+    /// Progress tokens on the board, taken ones are left as empty slots.
+    pub fn board_tokens(&self) -> &[Option<token::Id>] {
+        &self.state.tokens
+    }
+
+    /// Units to choose from in the current selection phase.
+    pub fn interactive_units(&self) -> &Units {
+        &self.state.interactive_units
+    }
+
+    /// Breakdown of the building price for the player on turn, see [`PriceQuote`].
+    pub fn quote_building(&self, building: building::Id) -> PriceQuote {
+        self.state.get_building_quote(&building)
+    }
+
+    /// Breakdown of the wonder price for the player on turn, see [`PriceQuote`].
+    pub fn quote_wonder(&self, wonder: wonder::Id) -> PriceQuote {
+        self.state.get_wonder_quote(&wonder)
+    }
+
+    pub(crate) fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn finish(&self) -> Option<&Finish> {
+        self.state.finish.as_ref()
+    }
+
+    /// Pantheon board, if the game is played with the expansion.
+    pub fn pantheon(&self) -> Option<&Board> {
+        self.state.pantheon.as_ref().map(|p| &p.board)
+    }
+
+    /// Senate of the Agora expansion, if the game is played with it.
+    pub fn agora(&self) -> Option<&agora::Board> {
+        self.state.agora.as_ref().map(|a| &a.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        economy::PriceSource,
+        state::Victory,
+        tests::game_11_actions,
+    };
+
+    #[test]
+    fn replay() {
+        let g = Game::from(game_11_actions()).expect("its ok");
+
+        assert_eq!(Phase::Over, g.phase());
+        assert_eq!([1, 2], g.players());
+        assert_eq!(Some(1), g.finish().map(|f| f.winner));
+        assert_eq!(33, g.city(1).unwrap().coins);
+        assert_eq!(19, g.city(2).unwrap().coins);
+        assert!(g.city(3).is_none());
+    }
+
+    #[test]
+    fn clock() {
+        let actions = game_11_actions();
+        let mut g = Game::from(actions[..1].to_vec()).expect("its ok");
+        g.set_clock(TimeControl::Fischer { base: 1000, increment: 100 }, 0);
+
+        let first = g.turn();
+        assert!(g.apply_at(first, actions[1].clone(), 300).is_ok());
+        assert_eq!(Some(800), g.remaining(first, 300));
+
+        let second = g.turn();
+        assert_ne!(first, second);
+        assert_eq!(Some(300), g.remaining(second, 1000));
+        assert_eq!(None, g.check_clock(1299));
+
+        // players cannot claim a timeout while the clock has time
+        for player in [first, second] {
+            assert_eq!(Err(Error::TimeoutByClockOnly), g.apply(player, Action::Timeout(second)));
+        }
+
+        // the move comes too late
+        let events = g.apply_at(second, actions[2].clone(), 1300).expect("its ok");
+        let finish = Finish { winner: first, victory: Victory::Timeout };
+
+        assert!(events.contains(&GameEvent::GameFinished(finish.clone())));
+        assert_eq!(Some(&finish), g.finish());
+        assert_eq!(Phase::Over, g.phase());
+
+        let mut g = Game::from(actions[..1].to_vec()).expect("its ok");
+        g.set_clock(TimeControl::PerMove(500), 0);
+
+        assert!(g.apply_at(first, actions[1].clone(), 400).is_ok());
+        assert_eq!(Some(500), g.remaining(first, 400));
+        assert!(g.check_clock(900).is_some());
+    }
+
+    #[test]
+    fn quotes_add_up() {
+        let actions = game_11_actions();
+        let mut discounted = vec![];
+
+        for len in 1..actions.len() {
+            let g = Game::from(actions[..len].to_vec()).expect("its ok");
+            let s = g.state();
+
+            if s.phase != Phase::Turn {
+                continue;
+            }
+
+            let mut quotes = g.playable_buildings().iter()
+                .map(|bid| {
+                    let price = if s.me().chains.contains(bid) {
+                        0
+                    } else {
+                        s.get_price(PayScope::from_building(bid), get_building(bid).cost.clone())
+                    };
+
+                    (price, g.quote_building(*bid))
+                })
+                .collect::<Vec<_>>();
+
+            quotes.extend(s.me().wonders.iter()
+                .filter(|(_, b)| b.is_none())
+                .map(|(wid, _)| (s.get_price(PayScope::Wonders, get_wonder(wid).cost.clone()), g.quote_wonder(*wid))));
+
+            for (price, quote) in quotes {
+                assert_eq!(price, quote.total, "after {} actions", len);
+
+                for r in &quote.resources {
+                    let discount = r.discounted.iter().map(|(_, count)| count).sum::<u8>();
+
+                    assert_eq!(r.required, r.produced + discount + r.bought);
+                    discounted.extend(r.discounted.iter().map(|(source, _)| *source));
+                }
+            }
+        }
+
+        assert!(discounted.contains(&Some(PriceSource::Building(building::Id::Caravansery))));
+
+        // trade goes to the opponent, the printed coins stay in the bank
+        let mut g = Game::from(actions[..40].to_vec()).expect("its ok");
+        let wid = g.state.me().wonders.iter().find(|(_, b)| b.is_none()).map(|(wid, _)| *wid).unwrap();
+        let before = g.quote_wonder(wid);
+        let enemy = g.state.players.enemy;
+        g.state.cities.get_mut(&enemy).unwrap().tokens.insert(token::Id::Economy);
+        let quote = g.quote_wonder(wid);
+
+        assert!(quote.total > quote.coins);
+        assert_eq!(0, before.to_opponent);
+        assert_eq!(quote.total - quote.coins, quote.to_opponent);
+    }
+}
@@ -0,0 +1,378 @@
+use crate::{
+    prelude::*,
+    clock::{Clock, Millis, TimeControl},
+    game::Game,
+    military::ConflictTrack,
+};
+
+// every n-th ply keeps a copy of the game, so navigation never replays from the very start
+const CHECKPOINT_INTERVAL: usize = 10;
+
+/// Tree of applied actions with a cursor on the current position.
+///
+/// Undo and redo move the cursor along the current line, pushing an action
+/// where the line already continues starts a variation.
+#[derive(Debug, Clone)]
+pub struct GameHistory {
+    nodes: Vec<Node>,
+    cursor: usize,
+    game: Game,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    // root has no action
+    action: Option<Action>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // child to follow on redo, the last visited one
+    selected: Option<usize>,
+    ply: usize,
+    checkpoint: Option<Game>,
+    // time the move took and the clock after it, none for untimed moves
+    spent: Option<Millis>,
+    clock: Option<Clock>,
+}
+
+impl Default for GameHistory {
+    fn default() -> Self {
+        Self::starting_from(Game::default())
+    }
+}
+
+impl GameHistory {
+    /// Empty history of a game played on the given conflict track, see [`Game::set_conflict_track`].
+    pub fn new(track: impl ConflictTrack + 'static) -> Self {
+        let mut game = Game::default();
+        game.set_conflict_track(track);
+
+        Self::starting_from(game)
+    }
+
+    /// Replays an action log, the first action is expected to be [`Action::Prepare`].
+    pub fn from(actions: Vec<Action>) -> Result<Self, Error> {
+        Self::default().replay(actions)
+    }
+
+    /// Appends an already validated action log to the current position.
+    pub fn replay(mut self, actions: Vec<Action>) -> Result<Self, Error> {
+        for action in actions {
+            let mut game = self.game.clone();
+            game.apply_trusted(action.clone())?;
+            self.advance(action, game, None);
+        }
+
+        Ok(self)
+    }
+
+    fn starting_from(game: Game) -> Self {
+        Self {
+            nodes: vec![Node {
+                action: None,
+                parent: None,
+                children: vec![],
+                selected: None,
+                ply: 0,
+                checkpoint: Some(game.clone()),
+                spent: None,
+                clock: None,
+            }],
+            cursor: 0,
+            game,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Number of actions applied to reach the current position.
+    pub fn ply(&self) -> usize {
+        self.nodes[self.cursor].ply
+    }
+
+    /// Actions from the start of the game to the current position.
+    pub fn line(&self) -> Vec<&Action> {
+        let mut line = self.get_path(self.cursor)
+            .into_iter()
+            .filter_map(|ind| self.nodes[ind].action.as_ref())
+            .collect::<Vec<_>>();
+
+        line.reverse();
+        line
+    }
+
+    /// Time spent on each action of [`GameHistory::line`], none for untimed moves.
+    pub fn spent(&self) -> Vec<Option<Millis>> {
+        let mut spent = self.get_path(self.cursor)
+            .into_iter()
+            .filter(|ind| self.nodes[*ind].action.is_some())
+            .map(|ind| self.nodes[ind].spent)
+            .collect::<Vec<_>>();
+
+        spent.reverse();
+        spent
+    }
+
+    /// Actions already played from the current position, the first one is the main line.
+    pub fn variations(&self) -> Vec<&Action> {
+        self.nodes[self.cursor].children
+            .iter()
+            .filter_map(|ind| self.nodes[*ind].action.as_ref())
+            .collect()
+    }
+
+    /// Applies the move made by `actor`. If the move was already played from
+    /// this position, its branch is reused with all the moves after it.
+    pub fn push(&mut self, actor: Nickname, action: Action) -> Result<Vec<GameEvent>, Error> {
+        let mut game = self.game.clone();
+        let events = game.apply(actor, action.clone())?;
+        self.advance(action, game, None);
+
+        Ok(events)
+    }
+
+    /// Starts the clocks from the current position, see [`Game::set_clock`].
+    pub fn set_clock(&mut self, control: TimeControl, now: Millis) {
+        self.game.set_clock(control, now);
+        self.nodes[self.cursor].clock = self.game.clock().cloned();
+    }
+
+    /// [`GameHistory::push`] timed at `now`, a move made after the time is out is
+    /// recorded as [`Action::Timeout`] of the player on turn.
+    pub fn push_at(&mut self, actor: Nickname, action: Action, now: Millis) -> Result<Vec<GameEvent>, Error> {
+        let mut game = self.game.clone();
+        let turn = game.turn();
+        let spent = game.clock().map(|clock| clock.elapsed(now));
+
+        let (action, events, spent) = match game.check_clock(now) {
+            Some(events) => (Action::Timeout(turn), events, spent),
+            None => {
+                let events = game.apply_at(actor, action.clone(), now)?;
+
+                (action, events, spent.filter(|_| actor == turn))
+            }
+        };
+
+        self.advance(action, game, spent);
+
+        Ok(events)
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.nodes[self.cursor].parent {
+            Some(parent) => {
+                self.move_to(parent);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.nodes[self.cursor].selected {
+            Some(child) => {
+                self.move_to(child);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves to the given ply of the current line.
+    pub fn goto(&mut self, ply: usize) -> bool {
+        let mut target = self.cursor;
+
+        while self.nodes[target].ply > ply {
+            target = self.nodes[target].parent.unwrap();
+        }
+
+        while self.nodes[target].ply < ply {
+            match self.nodes[target].selected {
+                Some(child) => target = child,
+                None => return false,
+            }
+        }
+
+        self.move_to(target);
+        true
+    }
+
+    fn advance(&mut self, action: Action, game: Game, spent: Option<Millis>) {
+        let existing = self.nodes[self.cursor].children
+            .iter()
+            .copied()
+            .find(|ind| self.nodes[*ind].action.as_ref() == Some(&action));
+
+        let child = existing.unwrap_or_else(|| {
+            let ply = self.ply() + 1;
+
+            self.nodes.push(Node {
+                action: Some(action),
+                parent: Some(self.cursor),
+                children: vec![],
+                selected: None,
+                ply,
+                checkpoint: ply.is_multiple_of(CHECKPOINT_INTERVAL).then(|| game.clone()),
+                spent: None,
+                clock: None,
+            });
+
+            let ind = self.nodes.len() - 1;
+            self.nodes[self.cursor].children.push(ind);
+
+            ind
+        });
+
+        // a replayed move keeps the time of the last play
+        self.nodes[child].spent = spent;
+        self.nodes[child].clock = game.clock().cloned();
+        self.nodes[self.cursor].selected = Some(child);
+        self.cursor = child;
+        self.game = game;
+    }
+
+    fn move_to(&mut self, target: usize) {
+        let path = self.get_path(target);
+        let from = path.iter()
+            .position(|ind| self.nodes[*ind].checkpoint.is_some())
+            .unwrap();
+
+        let mut game = self.nodes[path[from]].checkpoint.clone().unwrap();
+
+        for ind in path[..from].iter().rev() {
+            let action = self.nodes[*ind].action.clone().unwrap();
+            game.apply_trusted(action).expect("history keeps only applied actions");
+        }
+
+        game.restore_clock(self.nodes[target].clock.clone());
+
+        // keep redo on the line we are moving along
+        for pair in path.windows(2) {
+            self.nodes[pair[1]].selected = Some(pair[0]);
+        }
+
+        self.cursor = target;
+        self.game = game;
+    }
+
+    // node indexes from the given one up to the root
+    fn get_path(&self, mut ind: usize) -> Vec<usize> {
+        let mut path = vec![ind];
+
+        while let Some(parent) = self.nodes[ind].parent {
+            path.push(parent);
+            ind = parent;
+        }
+
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::Action::*,
+        building::Id::*,
+        military::{BaseTrack, TrackLayout},
+        tests::game_11_actions,
+    };
+
+    #[test]
+    fn navigation() {
+        let actions = game_11_actions();
+        let mut h = GameHistory::from(actions.clone()).expect("its ok");
+
+        assert_eq!(77, h.ply());
+        assert_eq!(actions.iter().collect::<Vec<_>>(), h.line());
+
+        assert!(h.undo());
+        assert_eq!(76, h.ply());
+        assert_eq!(Phase::BoardTokenSelection, h.game().phase());
+
+        assert!(h.redo());
+        assert!(!h.redo());
+        assert_eq!(Phase::Over, h.game().phase());
+
+        assert!(h.goto(13));
+        assert_eq!(Game::from(actions[..13].to_vec()).unwrap(), *h.game());
+
+        assert!(h.goto(40));
+        assert_eq!(Game::from(actions[..40].to_vec()).unwrap(), *h.game());
+
+        assert!(!h.goto(78));
+        assert_eq!(40, h.ply());
+
+        assert!(h.goto(0));
+        assert!(!h.undo());
+        assert_eq!(Phase::None, h.game().phase());
+    }
+
+    #[test]
+    fn variations() {
+        let actions = game_11_actions();
+        let mut h = GameHistory::from(actions[..14].to_vec()).expect("its ok");
+
+        assert!(h.undo());
+
+        let turn = h.game().turn();
+        let [p1, p2] = h.game().players();
+        let other = if turn == p1 { p2 } else { p1 };
+
+        assert_eq!(Err(Error::NotYourTurn), h.push(other, DiscardBuilding(Quarry)));
+        assert!(h.push(turn, DiscardBuilding(Quarry)).is_ok());
+        assert_eq!(vec![&ConstructBuilding(StonePit), &DiscardBuilding(Quarry)], h.line()[12..].to_vec());
+
+        assert!(h.undo());
+        assert_eq!(vec![&ConstructBuilding(Quarry), &DiscardBuilding(Quarry)], h.variations());
+
+        // redo follows the last visited variation
+        assert!(h.redo());
+        assert_eq!(Some(&&DiscardBuilding(Quarry)), h.line().last());
+
+        // playing the main line move again returns to it
+        assert!(h.undo());
+        assert!(h.push(turn, ConstructBuilding(Quarry)).is_ok());
+        assert_eq!(Game::from(actions[..14].to_vec()).unwrap(), *h.game());
+        assert_eq!(2, h.nodes[h.nodes[h.cursor].parent.unwrap()].children.len());
+    }
+
+    #[test]
+    fn timed_moves() {
+        let actions = game_11_actions();
+        let mut h = GameHistory::from(actions[..1].to_vec()).expect("its ok");
+        h.set_clock(TimeControl::Fischer { base: 1000, increment: 0 }, 0);
+
+        let first = h.game().turn();
+        assert!(h.push_at(first, actions[1].clone(), 300).is_ok());
+
+        let second = h.game().turn();
+        assert!(h.push_at(second, actions[2].clone(), 500).is_ok());
+        assert_eq!(vec![None, Some(300), Some(200)], h.spent());
+
+        // the clock goes back with the position
+        assert!(h.undo());
+        assert_eq!(Some(700), h.game().remaining(first, 300));
+        assert_eq!(Some(1000), h.game().clock().map(|c| c.remaining(second)));
+
+        // time is out before the move, the game ends instead
+        assert!(h.push_at(second, actions[2].clone(), 1400).is_ok());
+        assert_eq!(Some(&&Timeout(second)), h.line().last());
+        assert_eq!(Some(1100), h.spent().last().copied().flatten());
+    }
+
+    #[test]
+    fn keeps_conflict_track() {
+        let track = TrackLayout {
+            capital_pos: 12,
+            zones: BaseTrack.zones().to_vec(),
+        };
+        let mut h = GameHistory::new(track).replay(game_11_actions()[..25].to_vec()).expect("its ok");
+
+        assert!(h.goto(3));
+        assert_eq!(12, h.game().state().conflict_track().capital_pos());
+        assert!(h.goto(25));
+        assert_eq!(12, h.game().state().conflict_track().capital_pos());
+    }
+}
@@ -1,19 +1,62 @@
 
 mod prelude;
+mod bitset;
 mod effect;
 mod state;
 mod player;
-mod building;
-mod wonder;
-mod token;
+pub mod building;
+pub mod wonder;
+pub mod token;
+pub mod divinity;
+pub mod decree;
+pub mod conspiracy;
 mod economy;
+mod clock;
 mod military;
 mod deck;
+mod pantheon;
+mod agora;
 mod action;
 mod rule;
+mod game;
+mod history;
+mod error;
+mod event;
+pub mod notation;
+pub mod advisor;
+pub mod ai;
+pub mod solver;
+#[cfg(feature = "definitions")]
+pub mod definition;
+mod view;
 
 use prelude::*;
 
+pub use crate::{
+    action::{Action, Setup},
+    bitset::BitSet,
+    clock::{Clock, Millis, TimeControl},
+    agora::{Board as AgoraBoard, Chamber, InfluenceTrack, Setup as AgoraSetup},
+    deck::{Layout, Slot},
+    economy::{Bonus, Coins, Cost, Discount, PayScope, Points, PriceQuote, PriceSource, Resource, ResourceMap, ResourceQuote, Resources},
+    effect::Effect,
+    error::{Error, SetupError},
+    event::GameEvent,
+    game::Game,
+    history::GameHistory,
+    military::{BaseTrack, ConflictTrack, Track, TrackLayout, Zone},
+    pantheon::{Board as PantheonBoard, Setup as PantheonSetup},
+    player::Nickname,
+    state::{Age, Bank, Buildings, Cities, City, Finish, Phase, ScientificSymbol, Score, Units, Victory},
+    view::PlayerView,
+};
+
+#[cfg(feature = "definitions")]
+pub use crate::{
+    definition::Definitions,
+    error::DefinitionError,
+};
+
 pub trait BaseUnit {
     fn effects(&self) -> &Vec<Effect>;
 
@@ -34,15 +77,12 @@ pub trait BaseUnit {
     }
 }
 
-#[derive(Debug)]
-pub enum Error {
-    ActionNotAllowed,
-    NotEnoughCoins,
-}
-
-#[derive(Default)]
+#[derive(Default, Debug, Clone, Copy)]
 pub struct Options {
     pub with_promo_wonders: bool,
+    pub with_pantheon: bool,
+    // agora cards are provisional, see crate::agora
+    pub with_agora: bool,
 }
 
 #[cfg(test)]
@@ -60,9 +100,8 @@ mod tests {
         state::Score,
     };
 
-    #[test]
-    fn game_11() {
-        let actions = vec![
+    pub(crate) fn game_11_actions() -> Vec<Action> {
+        vec![
             Prepare(Setup{
                 p1: 1,
                 p2: 2,
@@ -165,6 +204,8 @@ mod tests {
                         ],
                     )
                 ]),
+                pantheon: None,
+                agora: None,
             }),
             PickWonder(TheTempleOfArtemis),//1
             PickWonder(TheHangingGardens),//2
@@ -242,8 +283,12 @@ mod tests {
             ConstructBuilding(Port),//74
             ConstructBuilding(Academy),//75
             PickBoardToken(Philosophy),//76
-        ];
-        let s = State::from(actions).expect("its ok");
+        ]
+    }
+
+    #[test]
+    fn game_11() {
+        let s = State::from(game_11_actions()).expect("its ok");
 
         // coins 33
         let expected1 = Score{
@@ -251,6 +296,9 @@ mod tests {
             science: 13,
             commercial: 6,
             guilds: 0,
+            temples: 0,
+            divinities: 0,
+            politics: 0,
             wonders: 9,
             tokens: 11,
             coins: 11,
@@ -264,6 +312,9 @@ mod tests {
             science: 2,
             commercial: 9,
             guilds: 10,
+            temples: 0,
+            divinities: 0,
+            politics: 0,
             wonders: 9,
             tokens: 0,
             coins: 6,
@@ -280,4 +331,38 @@ mod tests {
         assert_eq!(expected2, p2.score);
         assert_eq!(19, p2.coins);
     }
+
+    #[test]
+    fn legal_actions() {
+        let mut actions = game_11_actions().into_iter();
+        let mut s = State::default();
+
+        assert!(s.legal_actions().is_empty());
+
+        actions.next().unwrap().apply(&mut s).expect("its ok");
+
+        for action in actions {
+            let legal = s.legal_actions();
+
+            assert!(legal.contains(&action), "{:?} is not in {:?}", action, legal);
+            action.apply(&mut s).expect("its ok");
+        }
+
+        assert!(s.legal_actions().is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let actions = game_11_actions();
+        let s = State::from(actions.clone()).expect("its ok");
+
+        let json = serde_json::to_string(&actions).unwrap();
+        assert_eq!(actions, serde_json::from_str::<Vec<Action>>(&json).unwrap());
+
+        let json = serde_json::to_string(&s).unwrap();
+        assert_eq!(s, serde_json::from_str::<State>(&json).unwrap());
+
+        assert_eq!(r#"{"ConstructWonder":["Messe","Statue"]}"#, serde_json::to_string(&ConstructWonder(Messe, Statue)).unwrap());
+    }
 }
\ No newline at end of file
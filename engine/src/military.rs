@@ -1,73 +1,311 @@
+use std::fmt::Debug;
+use std::sync::{Arc, LazyLock};
 use crate::prelude::*;
 
 pub type Pos = u8;
 
-#[derive(Default, Debug)]
+/// Position of the conflict pawn on the side of a player.
+#[derive(Default, Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Track {
     pub pos: Pos,
     pub max_zone: usize,
+    // minerva stops the next move of the conflict pawn towards the capital
+    pub guarded: bool,
 }
 
-impl Track {
-    pub const CAPITAL_POS: u8 = 9;
-    // Токены штрафа это переменная которую можно выразить через эффекты,
-    // в агоре например, нет штрафов, там токены влияния
-    // Соответственно констуктор трека должен принимать токены зон, которые
-    // состоят из эффектов
-    // Либо есть трейт трека, с дефолтной реализацией, и в экстеншине идет
-    // нужный оверрайд
-    pub const ZONES: [Zone; 4] = [
-        // start pos, points, coins
-        Zone(0, 0, 0),
-        Zone(1, 2, 0),
-        Zone(3, 5, 2),
-        Zone(6, 10, 5),
-    ];
-
-    pub fn move_conflict_pawn(&mut self, s: &mut State, power: u8) -> (Coins, bool) {
-        let mut fine: Coins = 0;
-        let mut supremacy = false;
-        let enemy_pos = &mut s.enemy_mut().track.pos;
+/// Part of the track, effects are applied once for the player who moves the pawn into it first.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Zone {
+    pub start: Pos,
+    pub points: Points,
+    pub effects: Vec<Effect>,
+}
+
+/// Layout of the conflict track, the same for both sides.
+///
+/// Methods are the base game rules, implementors usually override [`ConflictTrack::zones`] only.
+pub trait ConflictTrack: Debug + Send + Sync {
+    fn capital_pos(&self) -> Pos {
+        9
+    }
+
+    // ordered by start, the first one starts at 0
+    fn zones(&self) -> &[Zone] {
+        &BASE_ZONES
+    }
+
+    fn get_zone_index(&self, pos: Pos) -> usize {
+        self.zones()
+            .iter()
+            .rposition(|zone| pos >= zone.start)
+            .unwrap_or_default()
+    }
+
+    fn get_points(&self, pos: Pos) -> Points {
+        self.zones()[self.get_zone_index(pos)].points
+    }
+}
+
+#[derive(Debug)]
+pub struct BaseTrack;
+
+impl ConflictTrack for BaseTrack {}
+
+static BASE_ZONES: LazyLock<Vec<Zone>> = LazyLock::new(|| {
+    vec![
+        Zone {
+            start: 0,
+            points: 0,
+            effects: vec![],
+        },
+        Zone {
+            start: 1,
+            points: 2,
+            effects: vec![],
+        },
+        Zone {
+            start: 3,
+            points: 5,
+            effects: vec![
+                Effect::MilitaryFine(2),
+            ],
+        },
+        Zone {
+            start: 6,
+            points: 10,
+            effects: vec![
+                Effect::MilitaryFine(5),
+            ],
+        },
+    ]
+});
+
+/// Track described by its capital position and zones with the base rules.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackLayout {
+    pub capital_pos: Pos,
+    pub zones: Vec<Zone>,
+}
+
+impl TrackLayout {
+    pub fn of(track: &dyn ConflictTrack) -> Self {
+        Self {
+            capital_pos: track.capital_pos(),
+            zones: track.zones().to_vec(),
+        }
+    }
+
+    // other methods of the track may be overridden, the layout keeps the base rules only
+    pub fn describes(&self, track: &dyn ConflictTrack) -> bool {
+        *self == Self::of(track)
+            && (0..=self.capital_pos).all(|pos| {
+                self.get_zone_index(pos) == track.get_zone_index(pos) && self.get_points(pos) == track.get_points(pos)
+            })
+    }
+}
+
+impl ConflictTrack for TrackLayout {
+    fn capital_pos(&self) -> Pos {
+        self.capital_pos
+    }
+
+    fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+}
+
+/// Track set for a single game, see [`crate::Game::set_conflict_track`].
+///
+/// Serialized as its [`TrackLayout`], a track with its own rules beyond the layout fails to serialize.
+#[derive(Debug, Clone)]
+pub struct CustomTrack(pub Arc<dyn ConflictTrack>);
+
+impl PartialEq for CustomTrack {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.capital_pos() == other.0.capital_pos() && self.0.zones() == other.0.zones()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CustomTrack {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let layout = TrackLayout::of(self.0.as_ref());
+
+        if !layout.describes(self.0.as_ref()) {
+            return Err(serde::ser::Error::custom(format!("conflict track {:?} has rules beyond its layout", self.0)));
+        }
+
+        layout.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CustomTrack {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        TrackLayout::deserialize(deserializer).map(|layout| Self(Arc::new(layout)))
+    }
+}
+
+impl State {
+    // custom one wins, expansions bring their own
+    pub fn conflict_track(&self) -> &dyn ConflictTrack {
+        match self.custom_track.as_ref() {
+            Some(track) => track.0.as_ref(),
+            None if self.agora.is_some() => &crate::agora::InfluenceTrack,
+            None => &BaseTrack,
+        }
+    }
+
+    pub fn set_conflict_track(&mut self, track: impl ConflictTrack + 'static) {
+        self.custom_track = Some(CustomTrack(Arc::new(track)));
+    }
 
-        if *enemy_pos >= power {
-            *enemy_pos -= power;
+    // returns the zone entered for the first time and whether the capital is reached
+    pub fn move_conflict_pawn(&mut self, power: u8) -> (Option<usize>, bool) {
+        if self.enemy().track.guarded {
+            self.enemy_mut().track.guarded = false;
 
-            return (fine, supremacy);
+            return (None, false);
         }
 
-        self.pos += power - *enemy_pos;
-        *enemy_pos = 0;
+        if self.enemy().track.pos >= power {
+            self.enemy_mut().track.pos -= power;
+
+            return (None, false);
+        }
+
+        let capital_pos = self.conflict_track().capital_pos();
+        let mut supremacy = false;
+
+        self.me_mut().track.pos += power - self.enemy().track.pos;
+        self.enemy_mut().track.pos = 0;
 
-        if self.pos >= Self::CAPITAL_POS {
-            self.pos = Self::CAPITAL_POS;
+        if self.me().track.pos >= capital_pos {
+            self.me_mut().track.pos = capital_pos;
             supremacy = true;
         }
 
-        let zone_index = self.get_zone_index();
+        let zone = self.conflict_track().get_zone_index(self.me().track.pos);
+
+        if zone > self.me().track.max_zone {
+            self.me_mut().track.max_zone = zone;
 
-        if zone_index > self.max_zone {
-            self.max_zone = zone_index;
-            fine = Self::ZONES[zone_index].2;
+            return (Some(zone), supremacy);
         }
 
-        (fine, supremacy)
+        (None, supremacy)
     }
 
-    pub fn get_points(&self) -> u8 {
-        Self::ZONES[self.get_zone_index()].1
+    // on behalf of the player on turn
+    pub(crate) fn apply_zone(&mut self, zone: usize) {
+        let effects = self.conflict_track().zones()[zone].effects.clone();
+
+        for effect in effects.iter() {
+            effect.apply(self);
+        }
     }
+}
 
-    pub fn get_zone_index(&self) -> usize {
-        let ind = [3,2,1].iter().find(|&&ind| {
-            self.pos >= Self::ZONES[ind].0
-        });
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::game_11_actions;
 
-        if let Some(v) = ind {
-            return *v
-        };
+    #[derive(Debug)]
+    struct LongTrack(Vec<Zone>);
 
-        0
+    impl ConflictTrack for LongTrack {
+        fn capital_pos(&self) -> Pos {
+            12
+        }
+
+        fn zones(&self) -> &[Zone] {
+            &self.0
+        }
     }
-}
 
-pub struct Zone(pub Pos, pub Points, pub Coins);
+    #[test]
+    fn base_zones() {
+        let track = BaseTrack;
+
+        assert_eq!([0, 1, 1, 2, 2, 2, 3, 3, 3, 3], (0..=9).map(|pos| track.get_zone_index(pos)).collect::<Vec<_>>()[..]);
+        assert_eq!(10, track.get_points(9));
+        assert_eq!(0, track.get_points(0));
+    }
+
+    #[test]
+    fn custom_track() {
+        let mut s = State::from(game_11_actions()[..9].to_vec()).expect("its ok");
+        s.set_conflict_track(LongTrack(vec![
+            Zone { start: 0, points: 0, effects: vec![] },
+            Zone { start: 4, points: 3, effects: vec![Effect::Coins(3)] },
+        ]));
+
+        let coins = s.me().coins;
+
+        assert_eq!((None, false), s.move_conflict_pawn(3));
+        assert_eq!((Some(1), false), s.move_conflict_pawn(1));
+        s.apply_zone(1);
+        assert_eq!(coins + 3, s.me().coins);
+        assert_eq!((None, true), s.move_conflict_pawn(9));
+        assert_eq!(12, s.me().track.pos);
+        assert_eq!(3, s.conflict_track().get_points(s.me().track.pos));
+    }
+
+    #[test]
+    fn loot_takes_next_zone() {
+        let mut s = State::from(game_11_actions()[..9].to_vec()).expect("its ok");
+        let coins = s.enemy().coins;
+
+        Effect::Loot.apply(&mut s);
+        assert_eq!(2, s.me().track.max_zone);
+        assert_eq!(coins - 2, s.enemy().coins);
+
+        s.set_conflict_track(LongTrack(vec![
+            Zone { start: 0, points: 0, effects: vec![] },
+            Zone { start: 4, points: 3, effects: vec![Effect::MilitaryFine(1)] },
+            Zone { start: 6, points: 6, effects: vec![Effect::Coins(3)] },
+        ]));
+        s.me_mut().track.max_zone = 0;
+        let coins = s.me().coins;
+
+        Effect::Loot.apply(&mut s);
+        Effect::Loot.apply(&mut s);
+        assert_eq!(2, s.me().track.max_zone);
+        assert_eq!(coins + 3, s.me().coins);
+
+        // nothing left to loot
+        Effect::Loot.apply(&mut s);
+        assert_eq!(coins + 3, s.me().coins);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn custom_track_round_trip() {
+        #[derive(Debug)]
+        struct DoublePoints;
+
+        impl ConflictTrack for DoublePoints {
+            fn get_points(&self, pos: Pos) -> Points {
+                2 * BaseTrack.get_points(pos)
+            }
+        }
+
+        let mut s = State::from(game_11_actions()[..9].to_vec()).expect("its ok");
+        s.set_conflict_track(LongTrack(vec![
+            Zone { start: 0, points: 0, effects: vec![] },
+            Zone { start: 4, points: 3, effects: vec![Effect::Coins(3)] },
+        ]));
+
+        let json = serde_json::to_string(&s).unwrap();
+        let loaded = serde_json::from_str::<State>(&json).unwrap();
+        assert_eq!(s, loaded);
+        assert_eq!(12, loaded.conflict_track().capital_pos());
+
+        s.set_conflict_track(DoublePoints);
+        assert!(serde_json::to_string(&s).is_err());
+    }
+}
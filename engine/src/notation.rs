@@ -0,0 +1,544 @@
+//! Line-oriented text record of a game.
+//!
+//! A record starts with tags describing the [`Setup`], followed by one move per line.
+//! Text after `#` is a comment.
+//!
+//! ```text
+//! [P1 "1"]
+//! [P2 "2"]
+//! [Wonders "TheHangingGardens TheTempleOfArtemis ..."]
+//! [BoardTokens "Economy Agriculture Philosophy Theology Law"]
+//! [RandomTokens "Urbanism Strategy Masonry"]
+//! [AgeI "Palisade Theater ..."]
+//! [AgeII "Dispensary CustomHouse ..."]
+//! [AgeIII "Port Academy ..."]
+//! [Divinities "Ra Anubis Isis Zeus ..."]  # pantheon only, piles top first
+//! [MythologyTokens "3:Greek 9:Roman ..."]  # layout slot:token
+//! [OfferingTokens "6:2 15:4 ..."]
+//! [Decrees "Glory Treasury ..."]  # agora only, chambers left to right
+//! [Conspiracies "Coup Arson ..."]  # pile top first
+//!
+//! PickWonder TheTempleOfArtemis
+//! ConstructWonder Messe Statue # comment
+//! SelectWhoBeginsTheNextAge 1
+//! ```
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Write};
+use std::str::FromStr;
+use crate::{
+    prelude::*,
+    action::Setup,
+    agora,
+    building,
+    divinity::{self, Mythology},
+    pantheon,
+};
+
+const TAGS: [&str; 13] = [
+    "P1",
+    "P2",
+    "Wonders",
+    "BoardTokens",
+    "RandomTokens",
+    "AgeI",
+    "AgeII",
+    "AgeIII",
+    "Divinities",
+    "MythologyTokens",
+    "OfferingTokens",
+    "Decrees",
+    "Conspiracies",
+];
+
+// line numbers start from 1
+#[derive(Debug, Eq, PartialEq)]
+pub enum ParseError {
+    MalformedTag(usize),
+    UnknownTag(usize),
+    DuplicateTag(usize),
+    UnexpectedTag(usize),
+    MissingTag(&'static str),
+    UnknownAction(usize),
+    UnknownUnit(usize),
+    InvalidPlayer(usize),
+    WrongArgumentsCount(usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedTag(line) => write!(f, "line {}: malformed tag", line),
+            Self::UnknownTag(line) => write!(f, "line {}: unknown tag", line),
+            Self::DuplicateTag(line) => write!(f, "line {}: tag is repeated", line),
+            Self::UnexpectedTag(line) => write!(f, "line {}: tag after the moves", line),
+            Self::MissingTag(tag) => write!(f, "tag {} is missing", tag),
+            Self::UnknownAction(line) => write!(f, "line {}: unknown action", line),
+            Self::UnknownUnit(line) => write!(f, "line {}: unknown unit", line),
+            Self::InvalidPlayer(line) => write!(f, "line {}: invalid player", line),
+            Self::WrongArgumentsCount(line) => write!(f, "line {}: wrong number of arguments", line),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn parse(record: &str) -> Result<Vec<Action>, ParseError> {
+    let mut tags: HashMap<&str, (usize, &str)> = Default::default();
+    let mut actions = vec![];
+
+    for (ind, line) in record.lines().enumerate() {
+        let line_no = ind + 1;
+        let line = line.split('#').next().unwrap_or_default().trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            if !actions.is_empty() {
+                return Err(ParseError::UnexpectedTag(line_no));
+            }
+
+            let (name, value) = parse_tag(line_no, line)?;
+
+            if tags.insert(name, (line_no, value)).is_some() {
+                return Err(ParseError::DuplicateTag(line_no));
+            }
+        } else {
+            actions.push(parse_action(line_no, line)?);
+        }
+    }
+
+    if !tags.is_empty() {
+        actions.insert(0, Action::Prepare(parse_setup(&tags)?));
+    }
+
+    Ok(actions)
+}
+
+pub fn print(actions: &[Action]) -> String {
+    let mut record = String::new();
+
+    for action in actions {
+        match action {
+            Action::Prepare(setup) => {
+                print_setup(&mut record, setup);
+            }
+            Action::Resign(p) => writeln!(record, "Resign {}", p).unwrap(),
+            Action::Timeout(p) => writeln!(record, "Timeout {}", p).unwrap(),
+            Action::SelectWhoBeginsTheNextAge(p) => writeln!(record, "SelectWhoBeginsTheNextAge {}", p).unwrap(),
+            Action::ConstructWonder(w, b) => writeln!(record, "ConstructWonder {:?} {:?}", w, b).unwrap(),
+            Action::ConstructBuilding(b) => writeln!(record, "ConstructBuilding {:?}", b).unwrap(),
+            Action::DiscardBuilding(b) => writeln!(record, "DiscardBuilding {:?}", b).unwrap(),
+            Action::DestructBuilding(b) => writeln!(record, "DestructBuilding {:?}", b).unwrap(),
+            Action::PickWonder(w) => writeln!(record, "PickWonder {:?}", w).unwrap(),
+            Action::PickBoardToken(t) => writeln!(record, "PickBoardToken {:?}", t).unwrap(),
+            Action::PickRandomToken(t) => writeln!(record, "PickRandomToken {:?}", t).unwrap(),
+            Action::PickTopLineBuilding(b) => writeln!(record, "PickTopLineBuilding {:?}", b).unwrap(),
+            Action::PickDiscardedBuilding(b) => writeln!(record, "PickDiscardedBuilding {:?}", b).unwrap(),
+            Action::PickReturnedBuildings(pick, give) => writeln!(record, "PickReturnedBuildings {:?} {:?}", pick, give).unwrap(),
+            Action::PickDivinity(d, space) => writeln!(record, "PickDivinity {:?} {}", d, space).unwrap(),
+            Action::ActivateDivinity(d) => writeln!(record, "ActivateDivinity {:?}", d).unwrap(),
+            Action::ActivateGate => writeln!(record, "ActivateGate").unwrap(),
+            Action::ConstructDiscardedWonder(w, b) => writeln!(record, "ConstructDiscardedWonder {:?} {:?}", w, b).unwrap(),
+            Action::DestructWonder(w) => writeln!(record, "DestructWonder {:?}", w).unwrap(),
+            Action::StealWonder(w) => writeln!(record, "StealWonder {:?}", w).unwrap(),
+            Action::DiscardLayoutBuilding(b) => writeln!(record, "DiscardLayoutBuilding {:?}", b).unwrap(),
+            Action::StealBuilding(b) => writeln!(record, "StealBuilding {:?}", b).unwrap(),
+            Action::PickScientificSymbol(symbol) => writeln!(record, "PickScientificSymbol {:?}", symbol).unwrap(),
+            Action::PlaceInfluence(chamber) => writeln!(record, "PlaceInfluence {}", chamber).unwrap(),
+            Action::MoveInfluence(from, to) => writeln!(record, "MoveInfluence {} {}", from, to).unwrap(),
+            Action::RemoveInfluence(chamber) => writeln!(record, "RemoveInfluence {}", chamber).unwrap(),
+            Action::PickConspiracy(c) => writeln!(record, "PickConspiracy {:?}", c).unwrap(),
+            Action::TriggerConspiracy(c) => writeln!(record, "TriggerConspiracy {:?}", c).unwrap(),
+        }
+    }
+
+    record
+}
+
+fn print_setup(record: &mut String, setup: &Setup) {
+    writeln!(record, "[P1 \"{}\"]", setup.p1).unwrap();
+    writeln!(record, "[P2 \"{}\"]", setup.p2).unwrap();
+    writeln!(record, "[Wonders \"{}\"]", join(&setup.wonders)).unwrap();
+    writeln!(record, "[BoardTokens \"{}\"]", join(&setup.board_tokens)).unwrap();
+    writeln!(record, "[RandomTokens \"{}\"]", join(&setup.random_tokens)).unwrap();
+
+    for age in Age::ALL {
+        if let Some(buildings) = setup.buildings.get(&age) {
+            writeln!(record, "[Age{:?} \"{}\"]", age, join(buildings)).unwrap();
+        }
+    }
+
+    if let Some(p) = setup.pantheon.as_ref() {
+        let divinities = Mythology::ALL.iter()
+            .flat_map(|m| p.divinities.get(m).cloned().unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        writeln!(record, "[Divinities \"{}\"]", join(&divinities)).unwrap();
+        writeln!(record, "[MythologyTokens \"{}\"]", join_slots(&p.mythology_tokens)).unwrap();
+        writeln!(record, "[OfferingTokens \"{}\"]", join_slots(&p.offering_tokens)).unwrap();
+    }
+
+    if let Some(a) = setup.agora.as_ref() {
+        writeln!(record, "[Decrees \"{}\"]", join(&a.decrees)).unwrap();
+        writeln!(record, "[Conspiracies \"{}\"]", join(&a.conspiracies)).unwrap();
+    }
+
+    writeln!(record).unwrap();
+}
+
+fn join<T: Debug>(units: &[T]) -> String {
+    units.iter()
+        .map(|id| format!("{:?}", id))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn join_slots<T: Debug>(tokens: &HashMap<usize, T>) -> String {
+    let mut slots = tokens.keys().copied().collect::<Vec<_>>();
+    slots.sort();
+
+    slots.iter()
+        .map(|slot| format!("{}:{:?}", slot, tokens[slot]))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_tag(line_no: usize, line: &str) -> Result<(&str, &str), ParseError> {
+    let (name, value) = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .and_then(|line| line.split_once(' '))
+        .ok_or(ParseError::MalformedTag(line_no))?;
+
+    let value = value.trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or(ParseError::MalformedTag(line_no))?;
+
+    if !TAGS.contains(&name) {
+        return Err(ParseError::UnknownTag(line_no));
+    }
+
+    Ok((name, value))
+}
+
+fn parse_setup(tags: &HashMap<&str, (usize, &str)>) -> Result<Setup, ParseError> {
+    let get = |name: &'static str| tags.get(name).copied().ok_or(ParseError::MissingTag(name));
+
+    let (line_no, p1) = get("P1")?;
+    let p1 = parse_player(line_no, p1)?;
+    let (line_no, p2) = get("P2")?;
+    let p2 = parse_player(line_no, p2)?;
+
+    let (line_no, wonders) = get("Wonders")?;
+    let wonders = parse_units(line_no, wonders, get_all_wonders().keys())?;
+
+    let (line_no, board_tokens) = get("BoardTokens")?;
+    let board_tokens = parse_units(line_no, board_tokens, get_all_tokens().keys())?;
+
+    let (line_no, random_tokens) = get("RandomTokens")?;
+    let random_tokens = parse_units(line_no, random_tokens, get_all_tokens().keys())?;
+
+    let mut buildings: HashMap<Age, Vec<building::Id>> = Default::default();
+
+    for (age, name) in Age::ALL.into_iter().zip(["AgeI", "AgeII", "AgeIII"]) {
+        let (line_no, ids) = get(name)?;
+        buildings.insert(age, parse_units(line_no, ids, get_all_buildings().keys())?);
+    }
+
+    let pantheon = if tags.contains_key("Divinities") {
+        Some(parse_pantheon(&get)?)
+    } else {
+        None
+    };
+
+    let agora = if tags.contains_key("Decrees") {
+        Some(parse_agora(&get)?)
+    } else {
+        None
+    };
+
+    Ok(Setup {
+        p1,
+        p2,
+        wonders,
+        board_tokens,
+        random_tokens,
+        buildings,
+        pantheon,
+        agora,
+    })
+}
+
+fn parse_pantheon<'a>(get: &impl Fn(&'static str) -> Result<(usize, &'a str), ParseError>) -> Result<pantheon::Setup, ParseError> {
+    let (line_no, value) = get("Divinities")?;
+    let mut divinities: HashMap<Mythology, Vec<divinity::Id>> = Default::default();
+
+    for id in parse_units(line_no, value, get_all_divinities().keys())? {
+        divinities.entry(get_divinity(&id).mythology).or_default().push(id);
+    }
+
+    let (line_no, value) = get("MythologyTokens")?;
+    let mythology_tokens = parse_slots(line_no, value, |name| parse_unit(line_no, name, Mythology::ALL.iter()))?;
+
+    let (line_no, value) = get("OfferingTokens")?;
+    let offering_tokens = parse_slots(line_no, value, |value| parse_number(line_no, value))?;
+
+    Ok(pantheon::Setup {
+        divinities,
+        mythology_tokens,
+        offering_tokens,
+    })
+}
+
+fn parse_agora<'a>(get: &impl Fn(&'static str) -> Result<(usize, &'a str), ParseError>) -> Result<agora::Setup, ParseError> {
+    let (line_no, value) = get("Decrees")?;
+    let decrees = parse_units(line_no, value, get_all_decrees().keys())?;
+
+    let (line_no, value) = get("Conspiracies")?;
+    let conspiracies = parse_units(line_no, value, get_all_conspiracies().keys())?;
+
+    Ok(agora::Setup {
+        decrees,
+        conspiracies,
+    })
+}
+
+fn parse_slots<T>(
+    line_no: usize,
+    value: &str,
+    parse_token: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<HashMap<usize, T>, ParseError> {
+    value.split_whitespace()
+        .map(|item| {
+            let (slot, token) = item.split_once(':').ok_or(ParseError::MalformedTag(line_no))?;
+
+            Ok((parse_number(line_no, slot)?, parse_token(token)?))
+        })
+        .collect()
+}
+
+fn parse_number<T: FromStr>(line_no: usize, value: &str) -> Result<T, ParseError> {
+    value.parse().map_err(|_| ParseError::UnknownUnit(line_no))
+}
+
+fn parse_action(line_no: usize, line: &str) -> Result<Action, ParseError> {
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let args = words.collect::<Vec<_>>();
+
+    let expected_args = match name {
+        "ConstructWonder" | "PickReturnedBuildings" | "PickDivinity" | "ConstructDiscardedWonder" | "MoveInfluence" => 2,
+        "ActivateGate" => 0,
+        _ => 1,
+    };
+
+    if args.len() != expected_args {
+        return Err(ParseError::WrongArgumentsCount(line_no));
+    }
+
+    let building = |ind: usize| parse_unit(line_no, args[ind], get_all_buildings().keys());
+    let wonder = |ind: usize| parse_unit(line_no, args[ind], get_all_wonders().keys());
+    let token = |ind: usize| parse_unit(line_no, args[ind], get_all_tokens().keys());
+    let divinity = |ind: usize| parse_unit(line_no, args[ind], get_all_divinities().keys());
+    let conspiracy = |ind: usize| parse_unit(line_no, args[ind], get_all_conspiracies().keys());
+    let chamber = |ind: usize| parse_number(line_no, args[ind]);
+
+    let action = match name {
+        "Resign" => Action::Resign(parse_player(line_no, args[0])?),
+        "Timeout" => Action::Timeout(parse_player(line_no, args[0])?),
+        "SelectWhoBeginsTheNextAge" => Action::SelectWhoBeginsTheNextAge(parse_player(line_no, args[0])?),
+        "ConstructWonder" => Action::ConstructWonder(wonder(0)?, building(1)?),
+        "ConstructBuilding" => Action::ConstructBuilding(building(0)?),
+        "DiscardBuilding" => Action::DiscardBuilding(building(0)?),
+        "DestructBuilding" => Action::DestructBuilding(building(0)?),
+        "PickWonder" => Action::PickWonder(wonder(0)?),
+        "PickBoardToken" => Action::PickBoardToken(token(0)?),
+        "PickRandomToken" => Action::PickRandomToken(token(0)?),
+        "PickTopLineBuilding" => Action::PickTopLineBuilding(building(0)?),
+        "PickDiscardedBuilding" => Action::PickDiscardedBuilding(building(0)?),
+        "PickReturnedBuildings" => Action::PickReturnedBuildings(building(0)?, building(1)?),
+        "PickDivinity" => Action::PickDivinity(divinity(0)?, parse_number(line_no, args[1])?),
+        "ActivateDivinity" => Action::ActivateDivinity(divinity(0)?),
+        "ActivateGate" => Action::ActivateGate,
+        "ConstructDiscardedWonder" => Action::ConstructDiscardedWonder(wonder(0)?, building(1)?),
+        "DestructWonder" => Action::DestructWonder(wonder(0)?),
+        "StealWonder" => Action::StealWonder(wonder(0)?),
+        "DiscardLayoutBuilding" => Action::DiscardLayoutBuilding(building(0)?),
+        "StealBuilding" => Action::StealBuilding(building(0)?),
+        "PickScientificSymbol" => Action::PickScientificSymbol(parse_unit(line_no, args[0], ScientificSymbol::ALL.iter())?),
+        "PlaceInfluence" => Action::PlaceInfluence(chamber(0)?),
+        "MoveInfluence" => Action::MoveInfluence(chamber(0)?, chamber(1)?),
+        "RemoveInfluence" => Action::RemoveInfluence(chamber(0)?),
+        "PickConspiracy" => Action::PickConspiracy(conspiracy(0)?),
+        "TriggerConspiracy" => Action::TriggerConspiracy(conspiracy(0)?),
+        _ => return Err(ParseError::UnknownAction(line_no)),
+    };
+
+    Ok(action)
+}
+
+fn parse_player(line_no: usize, value: &str) -> Result<Nickname, ParseError> {
+    value.parse().map_err(|_| ParseError::InvalidPlayer(line_no))
+}
+
+fn parse_units<'a, T>(line_no: usize, value: &str, all: impl Iterator<Item = &'a T> + Clone) -> Result<Vec<T>, ParseError>
+where
+    T: Debug + Copy + 'a,
+{
+    value.split_whitespace()
+        .map(|name| parse_unit(line_no, name, all.clone()))
+        .collect()
+}
+
+fn parse_unit<'a, T>(line_no: usize, name: &str, mut all: impl Iterator<Item = &'a T>) -> Result<T, ParseError>
+where
+    T: Debug + Copy + 'a,
+{
+    all.find(|id| format!("{:?}", id) == name)
+        .copied()
+        .ok_or(ParseError::UnknownUnit(line_no))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::game_11_actions;
+
+    const GAME_11: &str = r#"[P1 "1"]
+[P2 "2"]
+[Wonders "TheHangingGardens TheTempleOfArtemis TheColossus Messe ThePyramids StatueOfLiberty TheMausoleum TheSphinx"]
+[BoardTokens "Economy Agriculture Philosophy Theology Law"]
+[RandomTokens "Urbanism Strategy Masonry"]
+[AgeI "Palisade Theater Tavern Stable Altar Workshop ClayReserve GlassWorks LoggingCamp LumberYard Baths Quarry ClayPit ClayPool Scriptorium Garrison StonePit WoodReserve Pharmacist StoneReserve"]
+[AgeII "Dispensary CustomHouse CourtHouse Caravansery GlassBlower BrickYard School Laboratory Aqueduct ArcheryRange ParadeGround Brewery Statue HorseBreeders ShelfQuarry Library Walls SawMill Barracks DryingRoom"]
+[AgeIII "Port Academy Obelisk Observatory Fortifications Palace Senate Armory MagistratesGuild MerchantsGuild SiegeWorkshop ChamberOfCommerce Arsenal Pretorium Arena Lighthouse Gardens Pantheon MoneyLendersGuild TownHall"]
+
+PickWonder TheTempleOfArtemis
+PickWonder TheHangingGardens
+PickWonder TheColossus
+PickWonder Messe
+PickWonder TheSphinx
+PickWonder StatueOfLiberty
+PickWonder TheMausoleum
+PickWonder ThePyramids
+ConstructBuilding WoodReserve
+ConstructBuilding StoneReserve
+ConstructBuilding Scriptorium
+ConstructBuilding StonePit
+ConstructBuilding Quarry
+DiscardBuilding Garrison
+ConstructBuilding Pharmacist
+ConstructBuilding ClayPool
+ConstructBuilding LumberYard
+ConstructBuilding Baths
+DiscardBuilding ClayPit
+ConstructBuilding LoggingCamp
+ConstructBuilding GlassWorks
+ConstructBuilding Altar
+ConstructBuilding Workshop
+DiscardBuilding ClayReserve
+ConstructBuilding Tavern
+ConstructBuilding Stable
+ConstructBuilding Theater
+ConstructBuilding Palisade
+SelectWhoBeginsTheNextAge 1
+ConstructBuilding DryingRoom
+ConstructBuilding SawMill
+ConstructBuilding ShelfQuarry
+DiscardBuilding ParadeGround
+ConstructBuilding BrickYard
+ConstructBuilding Barracks
+ConstructBuilding Library
+PickBoardToken Theology
+ConstructBuilding Walls
+ConstructBuilding Brewery
+DiscardBuilding HorseBreeders
+ConstructWonder Messe Statue
+PickTopLineBuilding Dispensary
+PickBoardToken Economy
+ConstructBuilding Laboratory
+PickBoardToken Agriculture
+ConstructBuilding ArcheryRange
+ConstructBuilding Aqueduct
+ConstructBuilding GlassBlower
+ConstructBuilding School
+DiscardBuilding CourtHouse
+ConstructBuilding Caravansery
+ConstructBuilding CustomHouse
+SelectWhoBeginsTheNextAge 1
+ConstructWonder TheMausoleum MoneyLendersGuild
+PickDiscardedBuilding ParadeGround
+ConstructBuilding Lighthouse
+ConstructBuilding ChamberOfCommerce
+ConstructBuilding TownHall
+ConstructWonder ThePyramids Gardens
+ConstructBuilding Arsenal
+DiscardBuilding Pantheon
+DiscardBuilding Pretorium
+ConstructBuilding MerchantsGuild
+ConstructWonder StatueOfLiberty Senate
+PickReturnedBuildings Study Circus
+ConstructWonder TheTempleOfArtemis Palace
+ConstructBuilding Obelisk
+ConstructBuilding Arena
+ConstructBuilding SiegeWorkshop
+ConstructBuilding MagistratesGuild
+ConstructBuilding Armory
+ConstructBuilding Observatory
+ConstructBuilding Fortifications
+ConstructBuilding Port
+ConstructBuilding Academy
+PickBoardToken Philosophy
+"#;
+
+    #[test]
+    fn round_trip() {
+        let actions = parse(GAME_11).expect("its ok");
+
+        assert_eq!(game_11_actions(), actions);
+        assert_eq!(GAME_11, print(&actions));
+    }
+
+    #[test]
+    fn moves_only() {
+        let record = "
+            # continue after setup
+            ConstructBuilding WoodReserve
+            ConstructWonder Messe Statue # free comment
+
+            PickReturnedBuildings Study Circus
+            Resign 2
+            Timeout 1
+        ";
+
+        assert_eq!(
+            Ok(vec![
+                Action::ConstructBuilding(building::Id::WoodReserve),
+                Action::ConstructWonder(crate::wonder::Id::Messe, building::Id::Statue),
+                Action::PickReturnedBuildings(building::Id::Study, building::Id::Circus),
+                Action::Resign(2),
+                Action::Timeout(1),
+            ]),
+            parse(record),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(ParseError::UnknownAction(1)), parse("Build WoodReserve"));
+        assert_eq!(Err(ParseError::UnknownUnit(2)), parse("\nConstructBuilding Wood"));
+        assert_eq!(Err(ParseError::WrongArgumentsCount(1)), parse("ConstructWonder Messe"));
+        assert_eq!(Err(ParseError::InvalidPlayer(1)), parse("Resign me"));
+        assert_eq!(Err(ParseError::MalformedTag(1)), parse("[P1 1]"));
+        assert_eq!(Err(ParseError::UnknownTag(1)), parse("[Date \"today\"]"));
+        assert_eq!(Err(ParseError::DuplicateTag(2)), parse("[P1 \"1\"]\n[P1 \"2\"]"));
+        assert_eq!(Err(ParseError::UnexpectedTag(2)), parse("Resign 1\n[P1 \"1\"]"));
+        assert_eq!(Err(ParseError::MissingTag("P2")), parse("[P1 \"1\"]"));
+
+        let error: Box<dyn std::error::Error> = parse("Build WoodReserve").unwrap_err().into();
+        assert_eq!("line 1: unknown action", error.to_string());
+    }
+}
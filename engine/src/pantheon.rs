@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use crate::{
+    prelude::*,
+    divinity::{self, Mythology},
+};
+
+pub const SPACES_COUNT: usize = 6;
+// activation cost of each space for the player who starts the game, mirrored for the other one
+pub const SPACE_COSTS: [Coins; SPACES_COUNT] = [2, 3, 4, 4, 5, 6];
+pub const OFFERING_TOKENS: [Coins; 3] = [2, 3, 4];
+pub const DIVINITIES_DRAW_COUNT: usize = 2;
+// by temples count, each temple is worth the same
+pub const GRAND_TEMPLE_POINTS: [Points; 4] = [0, 5, 6, 7];
+// divinities can be activated since this age
+pub const ACTIVATION_AGE: Age = Age::II;
+// the gate costs twice its space
+pub const GATE_COST_RATE: Coins = 2;
+
+/// Public part of the Pantheon expansion: the board and the tokens left on the layout slots.
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Board {
+    pub spaces: [Option<divinity::Id>; SPACES_COUNT],
+    // who placed the divinity lying face down on a space, all are revealed when Age II starts
+    pub face_down: [Option<Nickname>; SPACES_COUNT],
+    // Age I layout slot -> token
+    pub mythology_tokens: HashMap<usize, Mythology>,
+    // Age II layout slot -> activation discount
+    pub offering_tokens: HashMap<usize, Coins>,
+    // space of the Gate, it takes the space left free when Age II starts
+    pub gate: Option<usize>,
+}
+
+impl Board {
+    pub fn find(&self, id: &divinity::Id) -> Option<usize> {
+        self.spaces.iter().position(|space| *space == Some(*id))
+    }
+
+    pub fn divinities(&self) -> Vec<divinity::Id> {
+        self.spaces.iter().flatten().copied().collect()
+    }
+
+    // divinities placed face down by the opponent are unknown
+    pub fn view_for(&self, player: Nickname) -> Self {
+        let mut board = self.clone();
+
+        for (space, placer) in board.spaces.iter_mut().zip(board.face_down.iter()) {
+            if placer.is_some_and(|p| p != player) {
+                *space = None;
+            }
+        }
+
+        board
+    }
+
+    pub fn free_spaces(&self) -> Vec<usize> {
+        (0..SPACES_COUNT)
+            .filter(|ind| self.spaces[*ind].is_none() && self.face_down[*ind].is_none() && self.gate != Some(*ind))
+            .collect()
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pantheon {
+    pub board: Board,
+
+    // clients invisible
+    pub piles: HashMap<Mythology, Vec<divinity::Id>>,
+}
+
+impl Pantheon {
+    pub fn new(setup: Setup) -> Self {
+        Self {
+            board: Board {
+                spaces: Default::default(),
+                face_down: Default::default(),
+                mythology_tokens: setup.mythology_tokens,
+                offering_tokens: setup.offering_tokens,
+                gate: None,
+            },
+            piles: setup.divinities,
+        }
+    }
+
+    pub fn draw(&mut self, m: Mythology) -> Vec<divinity::Id> {
+        let pile = self.piles.entry(m).or_default();
+        let count = pile.len().min(DIVINITIES_DRAW_COUNT);
+
+        pile.drain(..count).collect()
+    }
+
+    // the gate offers the top divinity of each pile
+    pub fn draw_tops(&mut self) -> Vec<divinity::Id> {
+        Mythology::ALL.iter()
+            .filter_map(|m| self.piles.get_mut(m).filter(|pile| !pile.is_empty()).map(|pile| pile.remove(0)))
+            .collect()
+    }
+
+    // divinities the gate did not activate go back on top of their piles
+    pub fn return_tops(&mut self, divinities: impl IntoIterator<Item = divinity::Id>) {
+        for id in divinities {
+            self.piles.entry(get_divinity(&id).mythology).or_default().insert(0, id);
+        }
+    }
+
+    pub fn has_gate_choice(&self) -> bool {
+        self.board.gate.is_some() && self.piles.values().any(|pile| !pile.is_empty())
+    }
+}
+
+/// Deal of the Pantheon expansion, part of [`crate::Setup`].
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Setup {
+    // draw piles, top first
+    pub divinities: HashMap<Mythology, Vec<divinity::Id>>,
+    pub mythology_tokens: HashMap<usize, Mythology>,
+    pub offering_tokens: HashMap<usize, Coins>,
+}
+
+impl State {
+    pub fn get_divinity_price(&self, space: usize) -> Coins {
+        let mut cost = if self.players.me == self.players.starts {
+            SPACE_COSTS[space]
+        } else {
+            SPACE_COSTS[SPACES_COUNT - 1 - space]
+        };
+
+        if self.pantheon.as_ref().is_some_and(|p| p.board.gate == Some(space)) {
+            cost *= GATE_COST_RATE;
+        }
+
+        let offering = self.me().offerings.iter().max().copied().unwrap_or_default();
+
+        cost.saturating_sub(offering + self.me().bank.divinity_discount)
+    }
+
+    // building leaves its layout slot, the player who took it gets the token placed there
+    pub(crate) fn take_slot_token(&mut self, slot: usize) {
+        let Some(pantheon) = self.pantheon.as_mut() else {
+            return;
+        };
+
+        match self.age {
+            Age::I => {
+                if let Some(m) = pantheon.board.mythology_tokens.remove(&slot) {
+                    let divinities = pantheon.draw(m);
+                    let player = self.players.me;
+
+                    self.me_mut().mythologies.push(m);
+                    self.me_mut().chains.push(m.temple());
+                    self.emit(GameEvent::MythologyTokenTaken { player, mythology: m });
+
+                    if !divinities.is_empty() {
+                        self.queue_post_effect(PostEffect::PickDivinity(player, divinities));
+                    }
+                }
+            }
+
+            Age::II => {
+                if let Some(discount) = pantheon.board.offering_tokens.remove(&slot) {
+                    self.me_mut().offerings.push(discount);
+                    self.emit(GameEvent::OfferingTokenTaken { player: self.players.me, discount });
+                }
+            }
+
+            Age::III => (),
+        }
+    }
+
+    // zeus discards buildings with the tokens on them
+    pub(crate) fn drop_slot_token(&mut self, slot: usize) {
+        if let Some(pantheon) = self.pantheon.as_mut() {
+            match self.age {
+                Age::I => {
+                    pantheon.board.mythology_tokens.remove(&slot);
+                }
+                Age::II => {
+                    pantheon.board.offering_tokens.remove(&slot);
+                }
+                Age::III => (),
+            }
+        }
+    }
+
+    pub(crate) fn reveal_divinities(&mut self) {
+        let Some(pantheon) = self.pantheon.as_mut() else {
+            return;
+        };
+
+        let revealed = (0..SPACES_COUNT)
+            .filter(|space| pantheon.board.face_down[*space].take().is_some())
+            .filter_map(|space| pantheon.board.spaces[space].map(|divinity| (space, divinity)))
+            .collect::<Vec<_>>();
+
+        for (space, divinity) in revealed {
+            self.emit(GameEvent::DivinityRevealed { divinity, space });
+        }
+
+        self.place_gate();
+    }
+
+    // the leftmost one if the board has several free spaces
+    fn place_gate(&mut self) {
+        let Some(pantheon) = self.pantheon.as_mut() else {
+            return;
+        };
+
+        if let Some(space) = pantheon.board.free_spaces().first().copied() {
+            pantheon.board.gate = Some(space);
+            self.emit(GameEvent::GatePlaced { space });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use rand::prelude::*;
+    use rand_chacha::ChaCha8Rng;
+    use super::*;
+    use crate::{notation, Options};
+
+    // plays random legal moves, every offered move must be accepted
+    fn random_game(seed: u64) -> (Vec<Action>, HashSet<Phase>) {
+        let o = Options {
+            with_promo_wonders: true,
+            with_pantheon: true,
+            ..Default::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut actions = vec![Action::Prepare(crate::Setup::from_seed(1, 2, o, seed))];
+        let mut phases = HashSet::new();
+        let mut s = State::from(actions.clone()).expect("its ok");
+
+        while s.phase != Phase::Over {
+            phases.insert(s.phase);
+
+            let action = s.legal_actions().choose(&mut rng).cloned().expect("moves are offered until the end");
+            action.clone().apply(&mut s).expect("legal move is accepted");
+            actions.push(action);
+        }
+
+        (actions, phases)
+    }
+
+    #[test]
+    fn random_games() {
+        let mut phases = HashSet::new();
+        let mut gates = 0;
+
+        for seed in 0..50 {
+            let (actions, seen) = random_game(seed);
+            gates += actions.iter().filter(|a| **a == Action::ActivateGate).count();
+            let s = State::from(actions.clone()).expect("its ok");
+
+            assert_eq!(Ok(actions.clone()), notation::parse(&notation::print(&actions)));
+            assert!(s.finish.is_some());
+
+            let mythologies = s.cities.values().map(|c| c.mythologies.len()).sum::<usize>();
+            assert_eq!(Mythology::ALL.len(), mythologies);
+
+            phases.extend(seen);
+        }
+
+        for phase in [
+            Phase::DivinitySelection,
+            Phase::DivinityActivationSelection,
+            Phase::DestructWonderSelection,
+            Phase::StealWonderSelection,
+            Phase::LayoutBuildingSelection,
+            Phase::StealBuildingSelection,
+            Phase::ScientificSymbolSelection,
+        ] {
+            assert!(phases.contains(&phase), "{:?} is never reached", phase);
+        }
+
+        assert!(gates > 0, "gate is never activated");
+    }
+
+    #[test]
+    fn divine_theater_skips_unknown_divinities() {
+        let (actions, _) = random_game(7);
+        let len = (1..actions.len())
+            .find(|len| {
+                let s = State::from(actions[..*len].to_vec()).expect("its ok");
+                let placed = &s.pantheon.as_ref().unwrap().board.face_down;
+                s.phase == Phase::Turn && placed.contains(&Some(s.players.me)) && placed.contains(&Some(s.players.enemy))
+            })
+            .expect("divinities are placed in age I");
+        let mut s = State::from(actions[..len].to_vec()).expect("its ok");
+        let board = s.pantheon.as_ref().unwrap().board.clone();
+
+        Effect::ActivateDivinity.apply(&mut s);
+
+        let Some(PostEffect::ActivateDivinity(_, offered)) = s.post_effects.last() else {
+            panic!("own divinities are offered");
+        };
+        assert_eq!(&board.view_for(s.players.me).divinities(), offered);
+        assert!(offered.len() < board.divinities().len());
+    }
+
+    #[test]
+    fn gate_offers_top_of_each_pile() {
+        let (actions, _) = random_game(7);
+        let len = (1..actions.len())
+            .find(|len| State::from(actions[..*len].to_vec()).expect("its ok").age == ACTIVATION_AGE)
+            .expect("age II is reached");
+        let mut s = State::from(actions[..len].to_vec()).expect("its ok");
+
+        let pantheon = s.pantheon.as_ref().unwrap();
+        let space = pantheon.board.gate.expect("gate takes the free space");
+        assert_eq!(None, pantheon.board.spaces[space]);
+        assert!(pantheon.board.free_spaces().is_empty());
+
+        let piles = pantheon.piles.clone();
+        let tops = Mythology::ALL.iter().filter_map(|m| piles[m].first().copied()).collect::<Vec<_>>();
+
+        s.players.me = s.players.starts;
+        s.me_mut().coins = 50;
+        s.me_mut().bank.divinity_discount = 0;
+        s.phase = Phase::Turn;
+        assert_eq!(SPACE_COSTS[space] * GATE_COST_RATE, s.get_divinity_price(space));
+        assert!(s.legal_actions().contains(&Action::ActivateGate));
+
+        Action::ActivateGate.apply(&mut s).expect("its ok");
+        assert_eq!(Phase::DivinityActivationSelection, s.phase);
+        assert_eq!(tops, s.interactive_units.divinities);
+        assert!(s.view_for(s.players.enemy).interactive_units.divinities.is_empty());
+
+        let chosen = tops[0];
+        Action::ActivateDivinity(chosen).apply(&mut s).expect("its ok");
+
+        let pantheon = s.pantheon.as_ref().unwrap();
+        assert_eq!(None, pantheon.board.gate);
+        assert!(s.cities.values().any(|c| c.divinities.contains(&chosen)));
+        assert_eq!(piles[&get_divinity(&chosen).mythology][1..], pantheon.piles[&get_divinity(&chosen).mythology]);
+        assert!(tops[1..].iter().all(|id| pantheon.piles[&get_divinity(id).mythology][0] == *id));
+        assert!(!pantheon.has_gate_choice());
+    }
+
+    #[test]
+    fn divinity_price() {
+        let (actions, _) = random_game(7);
+        let mut s = State::from(actions[..1].to_vec()).expect("its ok");
+        s.players.me = s.players.starts;
+
+        assert_eq!(2, s.get_divinity_price(0));
+        assert_eq!(6, s.get_divinity_price(5));
+
+        s.me_mut().offerings.push(3);
+        s.me_mut().bank.divinity_discount = 2;
+        assert_eq!(1, s.get_divinity_price(5));
+        assert_eq!(0, s.get_divinity_price(0));
+
+        s.players.next_turn();
+        assert_eq!(6, s.get_divinity_price(0));
+    }
+}
@@ -1,14 +1,19 @@
 pub use crate::{
     action::Action,
+    bitset::BitSet,
     building::{get as get_building, get_all as get_all_buildings},
+    conspiracy::{get as get_conspiracy, get_all as get_all_conspiracies},
     deck::{get_layout, Deck},
-    economy::{Bonus, Coins, Cost, Discount, PayScope, PriceList, Resource, Resources, Points},
+    decree::{get as get_decree, get_all as get_all_decrees},
+    divinity::{get as get_divinity, get_all as get_all_divinities},
+    economy::{Bonus, Coins, Cost, Discount, PayScope, PriceList, Resource, ResourceMap, Resources, Points},
     effect::{Effect, PostEffect},
+    error::Error,
+    event::GameEvent,
     player::Nickname,
     rule::*,
     state::{Age, City, Phase, ScientificSymbol, State, Victory},
     token::{get as get_token, get_all as get_all_tokens},
     wonder::{get as get_wonder, get_all as get_all_wonders},
     BaseUnit,
-    Error,
 };
\ No newline at end of file
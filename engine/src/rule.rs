@@ -2,7 +2,7 @@ pub const DEFAULT_RESOURCE_PRICE: u8 = 2;
 pub const DEFAULT_DISCARD_REWARD: u8 = 2;
 pub const STARTING_CITY_COINS: u8 = 7;
 pub const STARTING_TOKENS_COUNT: usize = 5;
-pub const RANDOM_TOKENS_COUNT: usize = 5;
+pub const RANDOM_TOKENS_COUNT: usize = 3;
 pub const WONDER_SELECTION_POOL_SIZE: u8 = 4;
 pub const WONDER_TOTAL_POOL_SIZE: u8 = WONDER_SELECTION_POOL_SIZE * 2;
 pub const WONDERS_CONSTRUCT_LIMIT: u8 = 7;
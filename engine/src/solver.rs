@@ -0,0 +1,349 @@
+//! Exact endgame search for Age III positions without hidden units.
+//!
+//! Negamax with alpha-beta pruning over every legal move, including selection phases
+//! and extra turns, the transposition table is kept between searches.
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use crate::{
+    prelude::*,
+    effect::PostEffect,
+    game::Game,
+    state::Finish,
+};
+
+// any supremacy is worth more than the widest civilian margin
+const SUPREMACY: i32 = 1000;
+const CIVILIAN: i32 = 500;
+const TABLE_LIMIT: usize = 4_000_000;
+
+/// Result of both players making the best moves until the end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    // moves to the end, the first one is for the player on turn
+    pub line: Vec<Action>,
+    pub finish: Finish,
+    // final total of the player on turn minus the one of the opponent
+    pub margin: i16,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    value: i32,
+    bound: Bound,
+    action: Option<Action>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Solver {
+    table: HashMap<u64, Entry>,
+    nodes: u64,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// None until the position is solvable, see [`is_solvable`].
+    pub fn solve(&mut self, game: &Game) -> Option<Solution> {
+        self.search(game.state())
+    }
+
+    // positions visited by all searches so far
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub(crate) fn search(&mut self, root: &State) -> Option<Solution> {
+        if !is_solvable(root) {
+            return None;
+        }
+
+        if self.table.len() > TABLE_LIMIT {
+            self.table.clear();
+        }
+
+        let mut s = root.clone();
+        let mut line = vec![];
+
+        // every move of the principal line is searched with the full window, so the values
+        // are exact, bounds kept in the table would not tell the best move apart
+        while s.finish.is_none() {
+            let player = s.players.me;
+            let mut best: Option<(i32, Action, State)> = None;
+
+            for action in s.legal_actions() {
+                let mut next = s.clone();
+                action.clone().apply(&mut next).expect("legal move is accepted");
+
+                let value = self.negamax(&next, -i32::MAX, i32::MAX);
+                let value = if next.players.me == player { value } else { -value };
+
+                if best.as_ref().is_none_or(|(best, _, _)| value > *best) {
+                    best = Some((value, action, next));
+                }
+            }
+
+            let (_, action, next) = best.expect("legal moves are offered until the end");
+            line.push(action);
+            s = next;
+        }
+
+        let total = |p| s.cities[&p].score.total as i16;
+        let me = root.players.me;
+        let enemy = root.players.enemy;
+
+        Some(Solution {
+            line,
+            finish: s.finish.clone().unwrap(),
+            margin: total(me) - total(enemy),
+        })
+    }
+
+    // value for the player on turn in s
+    fn negamax(&mut self, s: &State, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+
+        if let Some(finish) = &s.finish {
+            return value(s, finish);
+        }
+
+        let key = key(s);
+        let origin_alpha = alpha;
+        let mut actions = s.legal_actions();
+
+        if let Some(entry) = self.table.get(&key) {
+            match entry.bound {
+                Bound::Exact => return entry.value,
+                Bound::Lower => alpha = alpha.max(entry.value),
+                Bound::Upper => beta = beta.min(entry.value),
+            }
+
+            if alpha >= beta {
+                return entry.value;
+            }
+
+            // the best move of a previous search goes first
+            if let Some(pos) = actions.iter().position(|action| Some(action) == entry.action.as_ref()) {
+                actions.swap(0, pos);
+            }
+        }
+
+        let mut best = -i32::MAX;
+        let mut best_action = None;
+
+        for action in actions {
+            let mut next = s.clone();
+            action.clone().apply(&mut next).expect("legal move is accepted");
+
+            // extra turns and own selections keep the side
+            let value = if next.players.me == s.players.me {
+                self.negamax(&next, alpha, beta)
+            } else {
+                -self.negamax(&next, -beta, -alpha)
+            };
+
+            if value > best {
+                best = value;
+                best_action = Some(action);
+            }
+
+            alpha = alpha.max(value);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best <= origin_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.table.insert(key, Entry {
+            value: best,
+            bound,
+            action: best_action,
+        });
+
+        best
+    }
+}
+
+/// Age III with every remaining building face up and no way left to draw hidden units:
+/// random tokens, returned buildings, face down decrees, conspiracies of the pile or prepared ones.
+pub fn is_solvable(s: &State) -> bool {
+    if !s.age.is_last() || matches!(s.phase, Phase::None | Phase::Over) {
+        return false;
+    }
+
+    if !s.deck.get_face_down_buildings().is_empty() {
+        return false;
+    }
+
+    if s.cities.values().any(|c| c.conspiracies.iter().any(|c| c.is_some())) {
+        return false;
+    }
+
+    if s.agora.as_ref().is_some_and(|a| !a.decrees.is_empty()) {
+        return false;
+    }
+
+    // the gate draws from the piles
+    if s.pantheon.as_ref().is_some_and(|p| p.board.gate.is_some()) {
+        return false;
+    }
+
+    let is_drawn = s.post_effects.iter().any(|effect| matches!(
+        effect,
+        PostEffect::PickRandomToken(..) | PostEffect::PickReturnedBuildings(..) | PostEffect::Conspire(..)
+    ));
+
+    if is_drawn {
+        return false;
+    }
+
+    // effects of every unit which can still be put into play
+    let mut buildings = s.deck.get_remaining_buildings();
+    buildings.extend(s.buildings.discarded.iter());
+
+    let divinities = s.pantheon.as_ref()
+        .map(|p| p.board.divinities())
+        .unwrap_or_default();
+
+    let buildings = buildings.iter().flat_map(|id| get_building(id).effects.iter());
+    let wonders = s.cities.values()
+        .flat_map(|c| c.wonders.iter())
+        .filter(|(_, building)| building.is_none())
+        .flat_map(|(id, _)| get_wonder(id).effects.iter());
+    let tokens = s.tokens.iter()
+        .flatten()
+        .flat_map(|id| get_token(id).effects.iter());
+    let divinities = divinities.iter().flat_map(|id| get_divinity(id).effects.iter());
+
+    !buildings
+        .chain(wonders)
+        .chain(tokens)
+        .chain(divinities)
+        .any(|effect| matches!(effect, Effect::PickRandomToken | Effect::PickReturnedBuildings | Effect::Conspire))
+}
+
+// finished game for the player on turn in s
+fn value(s: &State, finish: &Finish) -> i32 {
+    let sign = if finish.winner == s.players.me { 1 } else { -1 };
+
+    match finish.victory {
+        Victory::Civilian => sign * CIVILIAN + s.me().score.total as i32 - s.enemy().score.total as i32,
+        _ => sign * SUPREMACY,
+    }
+}
+
+// equal positions print the same, events are drained after every move
+// everything the rest of the game depends on, prices, resources and scores follow from it
+fn key(s: &State) -> u64 {
+    let mut h = DefaultHasher::new();
+
+    s.phase.hash(&mut h);
+    s.players.me.hash(&mut h);
+    s.players.fallback.hash(&mut h);
+    s.play_again.hash(&mut h);
+    s.post_effects.hash(&mut h);
+    s.interactive_units.hash(&mut h);
+    s.tokens.hash(&mut h);
+    s.buildings.discarded.hash(&mut h);
+    s.deck.buildings.hash(&mut h);
+    s.deck.taken().hash(&mut h);
+
+    for (p, city) in s.cities.iter() {
+        p.hash(&mut h);
+        city.coins.hash(&mut h);
+        city.buildings.hash(&mut h);
+        city.wonders.hash(&mut h);
+        city.tokens.hash(&mut h);
+        city.track.hash(&mut h);
+        city.mythologies.hash(&mut h);
+        city.offerings.hash(&mut h);
+        city.divinities.hash(&mut h);
+        city.conspiracies.hash(&mut h);
+        city.triggered_conspiracies.hash(&mut h);
+    }
+
+    if let Some(pantheon) = s.pantheon.as_ref() {
+        pantheon.board.spaces.hash(&mut h);
+        pantheon.board.face_down.hash(&mut h);
+        pantheon.board.gate.hash(&mut h);
+    }
+
+    if let Some(agora) = s.agora.as_ref() {
+        agora.actions.hash(&mut h);
+
+        for chamber in agora.board.chambers.iter() {
+            chamber.decree.hash(&mut h);
+
+            for (p, _) in s.cities.iter() {
+                chamber.influence_of(*p).hash(&mut h);
+            }
+        }
+    }
+
+    h.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::game_11_actions;
+
+    // plain minimax over the same moves
+    fn minimax(s: &State) -> i32 {
+        if let Some(finish) = &s.finish {
+            return value(s, finish);
+        }
+
+        s.legal_actions().into_iter()
+            .map(|action| {
+                let mut next = s.clone();
+                action.apply(&mut next).expect("legal move is accepted");
+
+                if next.players.me == s.players.me {
+                    minimax(&next)
+                } else {
+                    -minimax(&next)
+                }
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn matches_minimax() {
+        let actions = game_11_actions();
+        let mut solver = Solver::new();
+
+        // the last face down building is revealed after 72 actions
+        assert!(!is_solvable(&State::from(actions[..71].to_vec()).expect("its ok")));
+
+        for len in 72..actions.len() {
+            let s = State::from(actions[..len].to_vec()).expect("its ok");
+            let solution = solver.search(&s).expect("nothing is hidden");
+            let mut end = s.clone();
+
+            for action in solution.line.clone() {
+                action.apply(&mut end).expect("legal move is accepted");
+            }
+
+            assert_eq!(Some(&solution.finish), end.finish.as_ref());
+            assert_eq!(minimax(&s), solver.negamax(&s, -i32::MAX, i32::MAX), "after {} actions", len);
+        }
+    }
+}
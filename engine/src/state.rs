@@ -1,31 +1,45 @@
-use std::cmp::Ordering;
+use std::cmp::{min, Ordering};
 use std::collections::{HashMap, HashSet};
 use crate::{
+    agora::{self, Agora},
     building::{self},
+    conspiracy,
     deck::Layout,
-    military::Track,
+    divinity::{self, Mythology},
+    economy::{assign_discounts, PriceQuote, PriceSource, ResourceQuote},
+    effect::get_constructed_wonders_count,
+    pantheon::{self, Pantheon},
+    military::{CustomTrack, Track},
     player::Finisher,
     prelude::*,
     token,
     wonder
 };
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct State {
     pub age: Age,
     pub phase: Phase,
     pub players: Players,
-    pub cities: HashMap<Nickname, City>,
+    pub cities: Cities,
     pub tokens: Vec<Option<token::Id>>,
     pub buildings: Buildings,
     pub interactive_units: Units,
     pub post_effects: Vec<PostEffect>,
     pub play_again: bool,
     pub finish: Option<Finish>,
+    pub pantheon: Option<Pantheon>,
+    pub agora: Option<Agora>,
+    pub custom_track: Option<CustomTrack>,
 
     // clients invisible
     pub deck: Deck,
     pub random_units: RandomUnits,
+
+    // events of the action being applied, drained by Action::apply
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub events: Vec<GameEvent>,
 }
 
 impl State {
@@ -58,83 +72,339 @@ impl State {
     }
 
     pub fn over(&mut self, finisher: Finisher, victory: Victory) {
-        if self.phase == Phase::Over {
+        over(self, finisher, victory);
+    }
+
+    pub(crate) fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub(crate) fn set_phase(&mut self, phase: Phase) {
+        if self.phase != phase {
+            self.phase = phase;
+            self.emit(GameEvent::PhaseChanged(phase));
+        }
+    }
+
+    pub(crate) fn add_coins(&mut self, player: Nickname, coins: Coins) {
+        if coins == 0 {
             return;
         }
 
-        self.phase = Phase::Over;
+        let city = self.cities.get_mut(&player).unwrap();
+        city.coins += coins;
 
-        let winner = match finisher {
-            Finisher::Winner(w) => w,
-            Finisher::Loser(l) => {
-                if l == self.players.me {
-                    self.players.enemy
-                } else {
-                    self.players.me
-                }
-            }
-        };
+        let total = city.coins;
+        self.emit(GameEvent::CoinsChanged { player, delta: coins as i16, coins: total });
+    }
 
-        self.finish = Some(Finish {
-            winner,
-            victory,
-        });
+    // takes as much as player has, returns coins actually taken
+    pub(crate) fn take_coins(&mut self, player: Nickname, coins: Coins) -> Coins {
+        let city = self.cities.get_mut(&player).unwrap();
+        let taken = min(coins, city.coins);
+
+        if taken == 0 {
+            return 0;
+        }
+
+        city.coins -= taken;
+
+        let total = city.coins;
+        self.emit(GameEvent::CoinsChanged { player, delta: -(taken as i16), coins: total });
+
+        taken
     }
 
-    pub fn pay(&mut self, scope: PayScope, mut cost: Cost) -> Result<(), Error> {
-        let cost_coins = cost.coins;
+    pub(crate) fn set_resource_price(&mut self, player: Nickname, resource: Resource, price: Coins) {
+        let prices = &mut self.cities.get_mut(&player).unwrap().bank.resource_price;
+
+        if prices.insert(resource, price) != price {
+            self.emit(GameEvent::ResourcePriceChanged { player, resource, price });
+        }
+    }
+
+    pub(crate) fn queue_post_effect(&mut self, effect: PostEffect) {
+        self.emit(GameEvent::PostEffectQueued { player: effect.actor(), phase: effect.phase() });
+        self.post_effects.push(effect);
+    }
 
+    pub fn get_price(&self, scope: PayScope, mut cost: Cost) -> Coins {
         cost.resources.iter_mut().
             for_each(|(r, count)| {
-                *count = count.saturating_sub(self.me().resources[r]);
+                *count = count.saturating_sub(self.me().resources[&r]);
+            });
+
+        self.me().bank.get_price(scope, cost)
+    }
+
+    // same total as get_price, split by where each resource comes from
+    pub fn get_price_quote(&self, scope: PayScope, cost: Cost) -> PriceQuote {
+        let city = self.me();
+        let mut left = cost.clone();
+
+        left.resources.iter_mut()
+            .for_each(|(r, count)| {
+                *count = count.saturating_sub(city.resources[&r]);
             });
 
-        let price = self.me().bank.get_price(scope, cost);
+        let shares = city.bank.get_discount_shares(scope, left.clone());
+        let sources = get_discount_sources(self);
+
+        let resources = cost.resources.iter()
+            .filter(|(_, required)| *required > 0)
+            .map(|(resource, required)| {
+                let discounted = shares.iter()
+                    .filter(|(_, r, _)| *r == resource)
+                    .map(|(ind, _, count)| (sources[*ind], *count))
+                    .collect::<Vec<_>>();
+
+                ResourceQuote {
+                    resource,
+                    required,
+                    produced: required - left.resources[&resource],
+                    bought: left.resources[&resource] - discounted.iter().map(|(_, count)| count).sum::<u8>(),
+                    discounted,
+                    unit_price: city.bank.resource_price[&resource],
+                    fixed_by: city.bank.has_fixed_resource_price(&resource)
+                        .then(|| get_fixed_price_source(self, resource))
+                        .flatten(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let total = cost.coins + resources.iter().map(|r| r.bought * r.unit_price).sum::<Coins>();
+        let to_opponent = if self.enemy().tokens.contains(&token::Id::Economy) {
+            total - cost.coins
+        } else {
+            0
+        };
+
+        PriceQuote {
+            coins: cost.coins,
+            resources,
+            chain: false,
+            total,
+            to_opponent,
+        }
+    }
 
-        if price > self.me().coins {
-            return Err(Error::NotEnoughCoins);
+    pub fn get_building_quote(&self, bid: &building::Id) -> PriceQuote {
+        if self.me().chains.contains(bid) {
+            return PriceQuote {
+                chain: true,
+                ..Default::default()
+            };
         }
 
-        self.me_mut().coins -= price;
+        self.get_price_quote(PayScope::from_building(bid), get_building(bid).cost.clone())
+    }
 
-        if self.enemy().tokens.contains(&token::Id::Economy) {
-            self.enemy_mut().coins += price - cost_coins;
+    pub fn get_wonder_quote(&self, wid: &wonder::Id) -> PriceQuote {
+        self.get_price_quote(PayScope::Wonders, get_wonder(wid).cost.clone())
+    }
+
+    // building leaves the layout, the token on its slot goes to the player who took it
+    pub(crate) fn pull_building(&mut self, bid: &building::Id) {
+        if let Some(slot) = self.deck.buildings.iter().position(|id| id == bid) {
+            self.take_slot_token(slot);
         }
 
-        Ok(())
+        self.deck.pull_building(bid);
     }
 
-    pub fn move_conflict_pawn(&mut self, power: u8) -> (Coins, bool) {
-        let mut fine: Coins = 0;
-        let mut supremacy = false;
+    // price must be checked before, see Action::validate
+    pub fn pay(&mut self, scope: PayScope, cost: Cost) {
+        let cost_coins = cost.coins;
+        let price = self.get_price(scope, cost);
 
-        if self.enemy().track.pos >= power {
-            self.enemy_mut().track.pos -= power;
+        self.take_coins(self.players.me, price);
 
-            return (fine, supremacy);
+        if self.enemy().tokens.contains(&token::Id::Economy) {
+            self.add_coins(self.players.enemy, price - cost_coins);
         }
+    }
+
+    // resign is allowed for both players at any time, so it is not listed
+    pub fn legal_actions(&self) -> Vec<Action> {
+        let units = &self.interactive_units;
+
+        match self.phase {
+            Phase::None | Phase::Over => vec![],
+
+            Phase::WondersSelection => units.wonders
+                .iter()
+                .flatten()
+                .map(|id| Action::PickWonder(*id))
+                .collect(),
+
+            Phase::Turn => self.get_turn_actions(),
+
+            Phase::WhoBeginsTheNextAgeSelection => self.players.members()
+                .into_iter()
+                .map(Action::SelectWhoBeginsTheNextAge)
+                .collect(),
+
+            Phase::BoardTokenSelection => units.tokens
+                .iter()
+                .map(|id| Action::PickBoardToken(*id))
+                .collect(),
+
+            Phase::RandomTokenSelection => units.tokens
+                .iter()
+                .map(|id| Action::PickRandomToken(*id))
+                .collect(),
+
+            Phase::DestructBuildingSelection => units.buildings
+                .iter()
+                .map(|id| Action::DestructBuilding(*id))
+                .collect(),
+
+            Phase::DiscardedBuildingSelection => units.buildings
+                .iter()
+                .map(|id| Action::PickDiscardedBuilding(*id))
+                .collect(),
+
+            Phase::TopLineBuildingSelection => units.buildings
+                .iter()
+                .map(|id| Action::PickTopLineBuilding(*id))
+                .collect(),
+
+            Phase::ReturnedBuildingSelection => units.buildings
+                .iter()
+                .flat_map(|pick| {
+                    units.buildings
+                        .iter()
+                        .filter(move |give| *give != pick)
+                        .map(|give| Action::PickReturnedBuildings(*pick, *give))
+                })
+                .collect(),
+
+            Phase::DivinitySelection => {
+                let spaces = self.pantheon.as_ref()
+                    .map(|p| p.board.free_spaces())
+                    .unwrap_or_default();
+
+                units.divinities
+                    .iter()
+                    .flat_map(|id| spaces.iter().map(|space| Action::PickDivinity(*id, *space)))
+                    .collect()
+            }
+
+            Phase::DivinityActivationSelection => units.divinities
+                .iter()
+                .map(|id| Action::ActivateDivinity(*id))
+                .collect(),
+
+            Phase::DiscardedWonderSelection => self.me().wonders
+                .iter()
+                .filter(|(_, b)| b.is_none())
+                .flat_map(|(wid, _)| units.buildings.iter().map(|bid| Action::ConstructDiscardedWonder(*wid, *bid)))
+                .collect(),
+
+            Phase::DestructWonderSelection => units.wonders
+                .iter()
+                .flatten()
+                .map(|id| Action::DestructWonder(*id))
+                .collect(),
+
+            Phase::StealWonderSelection => units.wonders
+                .iter()
+                .flatten()
+                .map(|id| Action::StealWonder(*id))
+                .collect(),
+
+            Phase::LayoutBuildingSelection => units.buildings
+                .iter()
+                .map(|id| Action::DiscardLayoutBuilding(*id))
+                .collect(),
+
+            Phase::StealBuildingSelection => units.buildings
+                .iter()
+                .map(|id| Action::StealBuilding(*id))
+                .collect(),
+
+            Phase::ScientificSymbolSelection => units.symbols
+                .iter()
+                .map(|symbol| Action::PickScientificSymbol(*symbol))
+                .collect(),
 
-        self.me_mut().track.pos += power - self.enemy().track.pos;
-        self.enemy_mut().track.pos = 0;
+            Phase::SenateSelection => self.get_senate_actions(),
 
-        if self.me_mut().track.pos >= Track::CAPITAL_POS {
-            self.me_mut().track.pos = Track::CAPITAL_POS;
-            supremacy = true;
+            Phase::InfluenceRemovalSelection => self.get_removal_actions(),
+
+            Phase::ConspiracySelection => {
+                let mut actions = units.conspiracies
+                    .iter()
+                    .map(|id| Action::PickConspiracy(*id))
+                    .collect::<Vec<_>>();
+
+                if self.get_influence_supply(self.players.me) > 0 {
+                    actions.extend((0..agora::CHAMBERS_COUNT).map(Action::PlaceInfluence));
+                }
+
+                actions
+            }
+        }
+    }
+
+    fn get_turn_actions(&self) -> Vec<Action> {
+        let mut actions = vec![];
+        let mut playable = self.buildings.playable.iter().copied().collect::<Vec<_>>();
+        playable.sort();
+
+        let total_wonders_constructed = get_constructed_wonders_count(self);
+
+        let affordable_wonders = self.me().wonders.iter()
+            .filter(|(wid, b)| {
+                b.is_none()
+                    && total_wonders_constructed < WONDERS_CONSTRUCT_LIMIT as usize
+                    && self.get_price(PayScope::Wonders, get_wonder(wid).cost.clone()) <= self.me().coins
+            })
+            .map(|(wid, _)| *wid)
+            .collect::<Vec<_>>();
+
+        for bid in playable {
+            let affordable = self.me().chains.contains(&bid)
+                || self.get_price(PayScope::from_building(&bid), get_building(&bid).cost.clone()) <= self.me().coins;
+
+            if affordable {
+                actions.push(Action::ConstructBuilding(bid));
+            }
+
+            actions.push(Action::DiscardBuilding(bid));
+
+            for wid in affordable_wonders.iter() {
+                actions.push(Action::ConstructWonder(*wid, bid));
+            }
         }
 
-        let zone_index = self.me_mut().track.get_zone_index();
+        if let Some(p) = self.pantheon.as_ref().filter(|_| self.age >= pantheon::ACTIVATION_AGE) {
+            for (space, id) in p.board.spaces.iter().enumerate() {
+                if let Some(id) = id {
+                    if self.get_divinity_price(space) <= self.me().coins {
+                        actions.push(Action::ActivateDivinity(*id));
+                    }
+                }
+            }
 
-        if zone_index > self.me_mut().track.max_zone {
-            self.me_mut().track.max_zone = zone_index;
-            fine = Track::ZONES[zone_index].2;
+            if let Some(space) = p.board.gate.filter(|_| p.has_gate_choice()) {
+                if self.get_divinity_price(space) <= self.me().coins {
+                    actions.push(Action::ActivateGate);
+                }
+            }
         }
 
-        (fine, supremacy)
+        // conspiracies are triggered before the move, the turn goes on
+        for id in self.me().conspiracies.iter().flatten() {
+            actions.push(Action::TriggerConspiracy(*id));
+        }
+
+        actions
     }
 
     fn resolve_next_turn(&mut self) {
         if self.deck.is_empty() && !self.age.is_last() {
-            self.phase = Phase::WhoBeginsTheNextAgeSelection;
+            self.set_phase(Phase::WhoBeginsTheNextAgeSelection);
             self.play_again = false;
 
             // if military parity last player continue
@@ -158,18 +428,72 @@ impl State {
     }
 }
 
-#[derive(Debug)]
+/// Both cities of the game by the nickname of the owner, empty until the game is prepared.
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cities(Vec<(Nickname, City)>);
+
+impl Cities {
+    pub fn new(p1: Nickname, p2: Nickname) -> Self {
+        Self(vec![
+            (p1, City::default()),
+            (p2, City::default()),
+        ])
+    }
+
+    pub fn get(&self, p: &Nickname) -> Option<&City> {
+        self.0.iter().find(|(owner, _)| owner == p).map(|(_, city)| city)
+    }
+
+    pub fn get_mut(&mut self, p: &Nickname) -> Option<&mut City> {
+        self.0.iter_mut().find(|(owner, _)| owner == p).map(|(_, city)| city)
+    }
+
+    pub fn contains_key(&self, p: &Nickname) -> bool {
+        self.get(p).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Nickname, &City)> {
+        self.0.iter().map(|(owner, city)| (owner, city))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &City> {
+        self.0.iter().map(|(_, city)| city)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut City> {
+        self.0.iter_mut().map(|(_, city)| city)
+    }
+}
+
+impl std::ops::Index<&Nickname> for Cities {
+    type Output = City;
+
+    fn index(&self, p: &Nickname) -> &City {
+        self.get(p).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct City {
     pub coins: Coins,
     pub resources: Resources,
     pub score: Score,
-    pub buildings: Vec<building::Id>,
+    // iterated and serialized in id order, the order of construction is not kept
+    pub buildings: BitSet<building::Id>,
     pub wonders: Vec<(wonder::Id, Option<building::Id>)>,
-    pub tokens: Vec<token::Id>,
+    pub tokens: BitSet<token::Id>,
     pub scientific_symbols: Vec<(ScientificSymbol, u8)>,
     pub chains: Vec<building::Id>,
     pub bank: Bank,
     pub track: Track,
+    pub mythologies: Vec<Mythology>,
+    pub offerings: Vec<Coins>,
+    pub divinities: Vec<divinity::Id>,
+    // prepared ones, hidden from the opponent in views
+    pub conspiracies: Vec<Option<conspiracy::Id>>,
+    pub triggered_conspiracies: Vec<conspiracy::Id>,
 }
 
 impl City {
@@ -197,25 +521,34 @@ impl Default for City {
     fn default() -> Self {
         Self {
             coins: STARTING_CITY_COINS,
-            resources: HashMap::from_iter(Resource::ALL.iter().map(|r| (*r, 0u8))),
+            resources: Resources::new(),
             score: Default::default(),
-            buildings: vec![],
+            buildings: BitSet::new(),
             wonders: vec![],
-            tokens: vec![],
+            tokens: BitSet::new(),
             scientific_symbols: vec![],
             chains: vec![],
             bank: Default::default(),
             track: Default::default(),
+            mythologies: vec![],
+            offerings: vec![],
+            divinities: vec![],
+            conspiracies: vec![],
+            triggered_conspiracies: vec![],
         }
     }
 }
 
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Score {
     pub civilian: u8,
     pub science: u8,
     pub commercial: u8,
     pub guilds: u8,
+    pub temples: u8,
+    pub divinities: u8,
+    pub politics: u8,
     pub wonders: u8,
     pub tokens: u8,
     pub coins: u8,
@@ -223,28 +556,35 @@ pub struct Score {
     pub total: u8,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Units {
     // should keep empty slot to show origin order on client
     pub wonders: Vec<Option<wonder::Id>>,
     pub buildings: Vec<building::Id>,
     pub tokens: Vec<token::Id>,
+    pub divinities: Vec<divinity::Id>,
+    pub symbols: Vec<ScientificSymbol>,
+    pub conspiracies: Vec<conspiracy::Id>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RandomUnits {
     pub buildings: HashMap<Age, Vec<building::Id>>,
     pub tokens: Vec<token::Id>,
     pub wonders: Vec<wonder::Id>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bank {
     pub discard_reward: u8,
     pub building_price: PriceList<building::Id>,
     pub wonder_price: PriceList<wonder::Id>,
-    pub resource_price: PriceList<Resource>,
+    pub resource_price: ResourceMap<Coins>,
     pub discounts: Vec<Discount>,
+    pub divinity_discount: Coins,
 }
 
 impl Bank {
@@ -254,7 +594,7 @@ impl Bank {
         cost.coins + cost.resources
             .iter()
             .fold(0, |acc, (resource, count)| {
-                acc + self.resource_price[resource] * count
+                acc + self.resource_price[&resource] * count
             })
     }
 
@@ -262,21 +602,31 @@ impl Bank {
         self.resource_price[r] == FIXED_RESOURCE_PRICE
     }
 
-    fn get_resources_ordered_by_price(&self) -> Vec<Resource> {
-        let mut items = self.resource_price.iter().collect::<Vec<_>>();
-        items.sort_by(|&a, &b| b.1.cmp(a.1));
+    // what each discount takes off the cost, by its index
+    pub(crate) fn get_discount_shares(&self, scope: PayScope, cost: Cost) -> Vec<(usize, Resource, u8)> {
+        let (indices, discounts): (Vec<_>, Vec<_>) = self.discounts.iter()
+            .enumerate()
+            .filter(|(_, item)| item.scope == PayScope::Global || item.scope == scope)
+            .unzip();
+
+        if discounts.is_empty() {
+            return vec![];
+        }
 
-        items.iter().map(|item| *item.0).collect()
+        assign_discounts(&discounts, cost.resources, &self.resource_price).into_iter()
+            .zip(indices)
+            .flat_map(|(share, ind)| {
+                Resource::ALL.into_iter()
+                    .filter(move |r| share[r] > 0)
+                    .map(move |r| (ind, r, share[&r]))
+            })
+            .collect()
     }
 
     fn discount(&self, scope: PayScope, cost: &mut Cost) {
-        let priority = self.get_resources_ordered_by_price();
-
-        self.discounts.iter()
-            .filter(|&item| item.scope == PayScope::Global || item.scope == scope)
-            .for_each(|discount| {
-                discount.apply(cost, &priority);
-            });
+        for (_, resource, count) in self.get_discount_shares(scope, cost.clone()) {
+            cost.resources[&resource] -= count;
+        }
     }
 }
 
@@ -286,22 +636,22 @@ impl Default for Bank {
             discard_reward: DEFAULT_DISCARD_REWARD,
             building_price: Default::default(),
             wonder_price: Default::default(),
-            resource_price: Resource::ALL
-                .iter()
-                .map(|r| (*r, DEFAULT_RESOURCE_PRICE))
-                .collect(),
+            resource_price: ResourceMap::filled(DEFAULT_RESOURCE_PRICE),
             discounts: Default::default(),
+            divinity_discount: 0,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Finish {
     pub winner: Nickname,
     pub victory: Victory,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Players {
     pub starts: Nickname,
     pub me: Nickname,
@@ -326,7 +676,8 @@ impl Players {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Buildings {
     pub layout: Layout,
     pub playable: HashSet<building::Id>,
@@ -356,7 +707,12 @@ pub fn after(s: &mut State) {
 
     if !has_post_effects && s.deck.is_empty() && !s.age.is_last() {
         s.age.next();
-        s.deck = Deck::new(get_layout(s.age), s.random_units.buildings[&s.age].clone())
+        s.deck = Deck::new(get_layout(s.age), s.random_units.buildings[&s.age].clone());
+        s.emit(GameEvent::AgeStarted(s.age));
+
+        if s.age == pantheon::ACTIVATION_AGE {
+            s.reveal_divinities();
+        }
     }
 
     refresh_buildings(s);
@@ -371,7 +727,7 @@ pub fn after(s: &mut State) {
     } else if let Some(p) = s.players.fallback {
         // if starts next age, origin turn resolve is priority
         if s.phase != Phase::WhoBeginsTheNextAgeSelection {
-            s.phase = Phase::Turn;
+            s.set_phase(Phase::Turn);
             s.players.set_turn(p);
         }
 
@@ -389,13 +745,15 @@ fn get_score(s: &mut State) -> Score {
     let city = s.me();
 
     for bid in city.buildings.iter() {
-        let points = get_building(bid).get_points(s);
+        let points = get_building(&bid).get_points(s);
 
-        match get_building(bid).kind {
+        match get_building(&bid).kind {
             building::Kind::Scientific => score.science += points,
             building::Kind::Civilian => score.civilian += points,
             building::Kind::Commercial => score.commercial += points,
             building::Kind::Guild => score.guilds += points,
+            building::Kind::GrandTemple => score.temples += points,
+            building::Kind::Politician | building::Kind::Conspirator => score.politics += points,
             _ => (),
         };
     }
@@ -407,15 +765,35 @@ fn get_score(s: &mut State) -> Score {
     }
 
     for tid in city.tokens.iter() {
-        score.tokens += get_token(tid).get_points(s);
+        score.tokens += get_token(&tid).get_points(s);
+    }
+
+    for did in city.divinities.iter() {
+        score.divinities += get_divinity(did).get_points(s);
+    }
+
+    for cid in city.triggered_conspiracies.iter() {
+        score.politics += get_conspiracy(cid).get_points(s);
+    }
+
+    if let Some(agora) = s.agora.as_ref() {
+        for ind in agora.board.controlled_by(s.players.me) {
+            // controlled chambers have their decree revealed
+            if let Some(decree) = agora.board.chambers[ind].decree {
+                score.politics += get_decree(&decree).get_points(s);
+            }
+        }
     }
 
     score.coins = city.coins / COINS_PER_POINT;
-    score.military = city.track.get_points();
+    score.military = s.conflict_track().get_points(city.track.pos);
     score.total = score.civilian
         + score.science
         + score.commercial
         + score.guilds
+        + score.temples
+        + score.divinities
+        + score.politics
         + score.wonders
         + score.tokens
         + score.coins
@@ -470,6 +848,72 @@ pub fn resolve_winner(s: &mut State) -> Nickname {
     }
 }
 
+// owned units with their effects, the ones which could grant a discount or a fixed price,
+// buildings and tokens go in id order
+fn get_price_sources(s: &State) -> Vec<(PriceSource, &'static [Effect])> {
+    let city = s.me();
+    let mut sources = vec![];
+
+    for bid in city.buildings.iter() {
+        sources.push((PriceSource::Building(bid), get_all_buildings()[&bid].effects.as_slice()));
+    }
+
+    for (wid, _) in city.wonders.iter().filter(|(_, b)| b.is_some()) {
+        sources.push((PriceSource::Wonder(*wid), get_all_wonders()[wid].effects.as_slice()));
+    }
+
+    for tid in city.tokens.iter() {
+        sources.push((PriceSource::Token(tid), get_all_tokens()[&tid].effects.as_slice()));
+    }
+
+    if let Some(agora) = s.agora.as_ref() {
+        let decrees = agora.board.chambers.iter()
+            .filter(|c| c.controller == Some(s.players.me))
+            .filter_map(|c| c.decree);
+
+        for did in decrees {
+            sources.push((PriceSource::Decree(did), get_all_decrees()[&did].effects.as_slice()));
+        }
+    }
+
+    sources
+}
+
+// by the index of the bank discount, equal discounts of different units are matched in the
+// order of get_price_sources, which may differ from the order they were gained
+fn get_discount_sources(s: &State) -> Vec<Option<PriceSource>> {
+    let mut sources = get_price_sources(s).into_iter()
+        .flat_map(|(source, effects)| {
+            effects.iter().filter_map(move |effect| match effect {
+                Effect::Discounter { scope, resources, count } => Some((source, Discount {
+                    scope: *scope,
+                    resources: resources.clone(),
+                    count: *count,
+                })),
+                _ => None,
+            })
+        })
+        .map(Some)
+        .collect::<Vec<_>>();
+
+    s.me().bank.discounts.iter()
+        .map(|discount| {
+            sources.iter_mut()
+                .find(|candidate| candidate.as_ref().is_some_and(|(_, d)| d == discount))
+                .and_then(|candidate| candidate.take())
+                .map(|(source, _)| source)
+        })
+        .collect()
+}
+
+pub(crate) fn get_fixed_price_source(s: &State, resource: Resource) -> Option<PriceSource> {
+    get_price_sources(s).into_iter()
+        .find(|(_, effects)| effects.iter().any(|effect| {
+            matches!(effect, Effect::FixedResourcePrice(resources) if resources.contains(&resource))
+        }))
+        .map(|(source, _)| source)
+}
+
 pub(crate) fn refresh_cities(s: &mut State) {
     let turn = s.players.me;
 
@@ -500,7 +944,7 @@ pub(crate) fn over(s: &mut State, finisher: Finisher, victory: Victory) {
         return;
     }
 
-    s.phase = Phase::Over;
+    s.set_phase(Phase::Over);
 
     let winner = match finisher {
         Finisher::Winner(w) => w,
@@ -513,14 +957,18 @@ pub(crate) fn over(s: &mut State, finisher: Finisher, victory: Victory) {
         }
     };
 
-    s.finish = Some(Finish {
+    let finish = Finish {
         winner,
         victory,
-    });
+    };
+
+    s.finish = Some(finish.clone());
+    s.emit(GameEvent::GameFinished(finish));
 }
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Age {
     #[default]
     I = 1,
@@ -544,7 +992,8 @@ impl Age {
     }
 }
 
-#[derive(Debug, Default,Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Default, Eq, PartialEq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Phase {
     #[default]
     None = 0,
@@ -558,9 +1007,21 @@ pub enum Phase {
     DiscardedBuildingSelection,
     TopLineBuildingSelection,
     ReturnedBuildingSelection,
+    DivinitySelection,
+    DivinityActivationSelection,
+    DiscardedWonderSelection,
+    DestructWonderSelection,
+    StealWonderSelection,
+    LayoutBuildingSelection,
+    StealBuildingSelection,
+    ScientificSymbolSelection,
+    SenateSelection,
+    InfluenceRemovalSelection,
+    ConspiracySelection,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScientificSymbol {
     Astrology = 1,
     Wheel,
@@ -571,11 +1032,25 @@ pub enum ScientificSymbol {
     Law,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl ScientificSymbol {
+    pub const ALL: [Self; 7] = [
+        Self::Astrology,
+        Self::Wheel,
+        Self::Sundial,
+        Self::Mortar,
+        Self::Compass,
+        Self::Writing,
+        Self::Law,
+    ];
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Victory {
     Civilian = 1,
     MilitarySupremacy,
     ScienceSupremacy,
     Resign,
     Timeout,
+    PoliticalSupremacy,
 }
\ No newline at end of file
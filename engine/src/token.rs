@@ -1,10 +1,15 @@
 use std::collections::HashMap;
 use std::sync::LazyLock;
+#[cfg(feature = "definitions")]
+use std::sync::OnceLock;
 use crate::{
     prelude::*,
+    bitset::Member,
 };
 
-#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
 pub enum Id {
     Agriculture = 1,
     Architecture,
@@ -16,9 +21,15 @@ pub enum Id {
     Strategy,
     Theology,
     Urbanism,
+
+    // new token of installed definitions, sorts after the built-in ones
+    #[cfg(feature = "definitions")]
+    Custom(u8),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct Unit {
     pub id: Id,
     pub effects: Vec<Effect>,
@@ -101,10 +112,49 @@ static REGISTRY: LazyLock<HashMap<Id, Unit>> = LazyLock::new(|| {
         .collect::<HashMap<_,_>>()
 });
 
+static ORDERED: LazyLock<Vec<Id>> = LazyLock::new(|| get_ordered(&REGISTRY));
+
+// definitions installed over the built-in set with their ids in order, see crate::definition
+#[cfg(feature = "definitions")]
+static INSTALLED: OnceLock<(HashMap<Id, Unit>, Vec<Id>)> = OnceLock::new();
+
+fn get_ordered(units: &HashMap<Id, Unit>) -> Vec<Id> {
+    let mut ids = units.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    ids
+}
+
+impl Member for Id {
+    #[cfg(feature = "definitions")]
+    fn all() -> &'static [Self] {
+        INSTALLED.get().map_or(&ORDERED, |(_, ids)| ids)
+    }
+
+    #[cfg(not(feature = "definitions"))]
+    fn all() -> &'static [Self] {
+        &ORDERED
+    }
+}
+
 pub fn get(id: &Id) -> &Unit {
-    REGISTRY.get(id).unwrap()
+    get_all().get(id).unwrap()
+}
+
+#[cfg(feature = "definitions")]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    INSTALLED.get().map_or(&REGISTRY, |(units, _)| units)
 }
 
+#[cfg(not(feature = "definitions"))]
 pub fn get_all() -> &'static HashMap<Id, Unit> {
     &REGISTRY
-}
\ No newline at end of file
+}
+
+// once per process, false when the set is already replaced
+#[cfg(feature = "definitions")]
+pub(crate) fn install(units: HashMap<Id, Unit>) -> bool {
+    let ids = get_ordered(&units);
+
+    INSTALLED.set((units, ids)).is_ok()
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use crate::{
+    prelude::*,
+    agora,
+    pantheon::Board,
+    state::{Buildings, Finish, Units},
+    token,
+};
+
+/// Part of the [`State`] a player is allowed to know.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerView {
+    pub player: Nickname,
+    pub age: Age,
+    pub phase: Phase,
+    pub turn: Nickname,
+    pub starts: Nickname,
+    pub cities: HashMap<Nickname, City>,
+    pub tokens: Vec<Option<token::Id>>,
+    pub buildings: Buildings,
+    pub interactive_units: Units,
+    pub play_again: bool,
+    pub finish: Option<Finish>,
+    pub pantheon: Option<Board>,
+    pub agora: Option<agora::Board>,
+}
+
+impl State {
+    pub fn view_for(&self, player: Nickname) -> PlayerView {
+        let mut interactive_units = self.interactive_units.clone();
+        let is_chooser = player == self.players.me;
+
+        // random tokens, returned buildings and drawn divinities come from hidden units,
+        // only the player who chooses can see them
+        match self.phase {
+            Phase::RandomTokenSelection if !is_chooser => {
+                interactive_units.tokens = vec![];
+            }
+
+            Phase::ReturnedBuildingSelection if !is_chooser => {
+                interactive_units.buildings = vec![];
+            }
+
+            Phase::DivinitySelection if !is_chooser => {
+                interactive_units.divinities = vec![];
+            }
+
+            // the gate offers divinities off the board
+            Phase::DivinityActivationSelection if !is_chooser => {
+                interactive_units.divinities.retain(|id| {
+                    self.pantheon.as_ref().is_some_and(|p| p.board.view_for(player).find(id).is_some())
+                });
+            }
+
+            Phase::ConspiracySelection if !is_chooser => {
+                interactive_units.conspiracies = vec![];
+            }
+
+            _ => (),
+        }
+
+        // prepared conspiracies of the opponent are face down
+        let mut cities = self.cities.iter()
+            .map(|(p, city)| (*p, city.clone()))
+            .collect::<HashMap<_, _>>();
+
+        for (p, city) in cities.iter_mut() {
+            if *p != player {
+                city.conspiracies.iter_mut().for_each(|c| *c = None);
+            }
+        }
+
+        PlayerView {
+            player,
+            age: self.age,
+            phase: self.phase,
+            turn: self.players.me,
+            starts: self.players.starts,
+            cities,
+            tokens: self.tokens.clone(),
+            buildings: Buildings {
+                layout: self.deck.get_public_layout(),
+                playable: self.buildings.playable.clone(),
+                discarded: self.buildings.discarded.clone(),
+            },
+            interactive_units,
+            play_again: self.play_again,
+            finish: self.finish.clone(),
+            pantheon: self.pantheon.as_ref().map(|p| p.board.view_for(player)),
+            agora: self.agora.as_ref().map(|a| a.board.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use rand_chacha::ChaCha8Rng;
+    use crate::{
+        deck::Slot,
+        tests::game_11_actions,
+        Options,
+    };
+
+    #[test]
+    fn hides_face_down_buildings() {
+        let s = State::from(game_11_actions()[..10].to_vec()).expect("its ok");
+        let view = s.view_for(s.players.enemy);
+
+        assert!(!s.deck.get_face_down_buildings().is_empty());
+        assert_eq!(s.buildings.layout, view.buildings.layout);
+        assert!(view.buildings.layout.iter().all(|slot| {
+            match slot {
+                Slot::FaceUp(id) => !s.deck.is_face_down(id),
+                _ => true,
+            }
+        }));
+    }
+
+    #[test]
+    fn hides_returned_buildings_from_opponent() {
+        // right after Statue of Liberty is constructed
+        let s = State::from(game_11_actions()[..65].to_vec()).expect("its ok");
+
+        assert_eq!(Phase::ReturnedBuildingSelection, s.phase);
+        assert!(!s.view_for(s.players.me).interactive_units.buildings.is_empty());
+        assert!(s.view_for(s.players.enemy).interactive_units.buildings.is_empty());
+    }
+
+    #[test]
+    fn hides_random_tokens_from_opponent() {
+        let mut s = State::from(game_11_actions()[..10].to_vec()).expect("its ok");
+        s.phase = Phase::RandomTokenSelection;
+        s.interactive_units.tokens = s.random_units.tokens.clone();
+
+        assert_eq!(s.random_units.tokens, s.view_for(s.players.me).interactive_units.tokens);
+        assert!(s.view_for(s.players.enemy).interactive_units.tokens.is_empty());
+    }
+
+    #[test]
+    fn hides_face_down_divinities_from_opponent() {
+        let o = Options {
+            with_pantheon: true,
+            ..Default::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let mut s = State::from(vec![Action::Prepare(crate::Setup::from_seed(1, 2, o, 5))]).expect("its ok");
+        let face_down = |s: &State| s.pantheon.as_ref().unwrap().board.face_down;
+
+        while face_down(&s).iter().all(Option::is_none) {
+            let action = s.legal_actions().choose(&mut rng).cloned().expect("its ok");
+            action.apply(&mut s).expect("its ok");
+        }
+
+        let space = face_down(&s).iter().position(Option::is_some).unwrap();
+        let placer = face_down(&s)[space].unwrap();
+        let opponent = s.players.members().into_iter().find(|p| *p != placer).unwrap();
+        let board = |s: &State, p| s.view_for(p).pantheon.unwrap();
+
+        assert_eq!(Age::I, s.age);
+        assert_eq!(s.pantheon.as_ref().unwrap().board.spaces[space], board(&s, placer).spaces[space]);
+        assert_eq!(None, board(&s, opponent).spaces[space]);
+        assert!(!board(&s, opponent).free_spaces().contains(&space));
+
+        while s.age == Age::I {
+            let action = s.legal_actions().choose(&mut rng).cloned().expect("its ok");
+            action.apply(&mut s).expect("its ok");
+        }
+
+        assert!(face_down(&s).iter().all(Option::is_none));
+        assert_eq!(board(&s, placer), board(&s, opponent));
+    }
+}
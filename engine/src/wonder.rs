@@ -1,11 +1,15 @@
 use std::collections::HashMap;
 use std::sync::LazyLock;
+#[cfg(feature = "definitions")]
+use std::sync::OnceLock;
 use crate::{
     prelude::*,
     building,
 };
 
-#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
 pub enum Id {
     TheAppianWay = 1,
     CircusMaximus,
@@ -21,6 +25,14 @@ pub enum Id {
     TheTempleOfArtemis,
     Messe,
     StatueOfLiberty,
+    DivineTheater,
+    Sanctuary,
+    CuriaJulia,
+    Knossos,
+
+    // new wonder of installed definitions, part of the base game pool
+    #[cfg(feature = "definitions")]
+    Custom(u8),
 }
 
 impl Id {
@@ -28,8 +40,21 @@ impl Id {
         Self::Messe,
         Self::StatueOfLiberty,
     ];
+
+    pub const PANTHEON: [Self;2] = [
+        Self::DivineTheater,
+        Self::Sanctuary,
+    ];
+
+    pub const AGORA: [Self;2] = [
+        Self::CuriaJulia,
+        Self::Knossos,
+    ];
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct Unit {
     pub id: Id,
     pub cost: Cost,
@@ -270,16 +295,95 @@ static REGISTRY: LazyLock<HashMap<Id, Unit>> = LazyLock::new(|| {
                 Effect::Points(5),
             ],
         },
+        Unit{
+            id: Id::DivineTheater,
+            cost: Cost{
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Papyrus, 2),
+                    (Resource::Glass, 1),
+                    (Resource::Wood, 2),
+                ])
+            },
+            effects: vec![
+                Effect::ActivateDivinity,
+                Effect::Points(2),
+            ],
+        },
+        Unit{
+            id: Id::Sanctuary,
+            cost: Cost{
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Glass, 1),
+                    (Resource::Papyrus, 1),
+                    (Resource::Stone, 2),
+                ])
+            },
+            effects: vec![
+                Effect::DivinityDiscount(2),
+                Effect::PlayAgain,
+                Effect::Points(2),
+            ],
+        },
+        // provisional agora wonders, see crate::agora
+        Unit{
+            id: Id::CuriaJulia,
+            cost: Cost{
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Stone, 2),
+                    (Resource::Wood, 1),
+                    (Resource::Papyrus, 1),
+                ])
+            },
+            effects: vec![
+                Effect::RemoveInfluence(1),
+                Effect::Senate(1),
+                Effect::Points(3),
+            ],
+        },
+        Unit{
+            id: Id::Knossos,
+            cost: Cost{
+                coins: 0,
+                resources: Resources::from([
+                    (Resource::Clay, 2),
+                    (Resource::Glass, 1),
+                    (Resource::Wood, 1),
+                ])
+            },
+            effects: vec![
+                Effect::Senate(2),
+                Effect::Points(2),
+            ],
+        },
     ]
         .into_iter()
         .map(|unit| (unit.id, unit))
         .collect::<HashMap<_,_>>()
 });
 
+// definitions installed over the built-in set, see crate::definition
+#[cfg(feature = "definitions")]
+static INSTALLED: OnceLock<HashMap<Id, Unit>> = OnceLock::new();
+
 pub fn get(id: &Id) -> &Unit {
-    REGISTRY.get(id).unwrap()
+    get_all().get(id).unwrap()
+}
+
+#[cfg(feature = "definitions")]
+pub fn get_all() -> &'static HashMap<Id, Unit> {
+    INSTALLED.get().unwrap_or(&REGISTRY)
 }
 
+#[cfg(not(feature = "definitions"))]
 pub fn get_all() -> &'static HashMap<Id, Unit> {
     &REGISTRY
-}
\ No newline at end of file
+}
+
+// once per process, false when the set is already replaced
+#[cfg(feature = "definitions")]
+pub(crate) fn install(units: HashMap<Id, Unit>) -> bool {
+    INSTALLED.set(units).is_ok()
+}
@@ -0,0 +1,65 @@
+//! Installed definitions replace the registries for the whole process, so they are tested apart
+//! from the unit tests.
+#![cfg(feature = "definitions")]
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use engine::{building, notation, token, wonder, Action, Age, Definitions, Effect, Game, Options, Phase, Setup};
+
+#[test]
+fn games_with_new_units() {
+    let mut definitions = Definitions::from_json(r#"{
+        "buildings": [{
+            "id": {"Custom": 1},
+            "age": "I",
+            "kind": "Civilian",
+            "cost": {"coins": 1, "resources": {"Wood": 1}},
+            "effects": [{"Points": 2}, {"Chain": {"Custom": 2}}]
+        }, {
+            "id": {"Custom": 2},
+            "age": "II",
+            "kind": "Civilian",
+            "cost": {"coins": 0, "resources": {"Stone": 2}},
+            "effects": [{"Points": 5}]
+        }],
+        "wonders": [{
+            "id": {"Custom": 1},
+            "cost": {"coins": 0, "resources": {"Wood": 1, "Clay": 1}},
+            "effects": [{"Coins": 3}, "PlayAgain"]
+        }],
+        "tokens": [{"id": {"Custom": 1}, "effects": [{"Coins": 8}]}]
+    }"#).expect("valid definitions");
+
+    // a built-in building moves to another age
+    let mut stable = building::get(&building::Id::Stable).clone();
+    stable.age = Age::II;
+    stable.effects.retain(|effect| !matches!(effect, Effect::Chain(_)));
+    definitions.buildings.push(stable);
+
+    definitions.install().expect("installed before any game");
+
+    let mut dealt = [false; 3];
+
+    for seed in 0..40 {
+        let setup = Setup::from_seed(1, 2, Options::default(), seed);
+        assert_eq!(Ok(()), setup.validate());
+        assert!(!setup.buildings[&Age::I].contains(&building::Id::Stable));
+
+        dealt[0] |= setup.buildings[&Age::I].contains(&building::Id::Custom(1));
+        dealt[1] |= setup.wonders.contains(&wonder::Id::Custom(1));
+        dealt[2] |= setup.board_tokens.contains(&token::Id::Custom(1));
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut actions = vec![Action::Prepare(setup.clone())];
+        let mut game = Game::new(setup).expect("its ok");
+
+        while game.phase() != Phase::Over {
+            let action = game.legal_actions().choose(&mut rng).cloned().expect("moves are offered until the end");
+            game.apply(game.turn(), action.clone()).expect("legal move is accepted");
+            actions.push(action);
+        }
+
+        assert_eq!(Ok(actions.clone()), notation::parse(&notation::print(&actions)));
+    }
+
+    assert_eq!([true; 3], dealt);
+}